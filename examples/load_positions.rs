@@ -10,6 +10,6 @@ fn main() {
 
 fn run(path: &str) -> Result<(), Box<dyn StdError>> {
     let (document, buffers) = models_cv::gltf::load(path);
-    let _ = models_cv::gltf::load_vertex_positions(&document,&buffers);
+    let _ = models_cv::gltf::load_vertex_positions::<f32>(&document,&buffers)?;
     Ok(())
 }
\ No newline at end of file
@@ -14,7 +14,7 @@ fn main() {
     if let Some(path) = std::env::args().nth(1) {
         if path.ends_with(".gltf") {
             let (document, buffers) = models_cv::gltf::load(&path);
-            let points = models_cv::gltf::load_vertex_positions(&document,&buffers);
+            let points = models_cv::gltf::load_vertex_positions(&document,&buffers).expect("Unsupported position data");
             render_mesh(&points);
         } else if path.ends_with(".obj") {
             let model = models_cv::obj::load(&path);
@@ -26,15 +26,15 @@ fn main() {
     }
 }
 
-fn render_mesh(points: &Vec<Vec<Vector3<f32>>>) -> () {
-    let scene_capacity: usize = points.iter().map(|vec| vec.iter().map(|ps| ps.len())).flatten().sum();
+fn render_mesh(points: &[Vec<Vector3<f32>>]) {
+    let scene_capacity: usize = points.iter().flat_map(|vec| vec.iter().map(|ps| ps.len())).sum();
 
     let mut window = Window::new("Gltf Model");
     let mut scene_center = Vector3::<f32>::new(0.0, 0.0, 0.0);
     println!("WARN: Assuming Triangle Mode!");
 
     for vertices in points {
-        let vertices_kiss3d = vertices.into_iter().map(|v| {
+        let vertices_kiss3d = vertices.iter().map(|v| {
             scene_center.x += v.x;
             scene_center.y += v.y;
             scene_center.z += v.z;
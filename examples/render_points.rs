@@ -11,7 +11,7 @@ fn main() {
     if let Some(path) = std::env::args().nth(1) {
         if path.ends_with(".gltf") {
             let (document, buffers) = models_cv::gltf::load(&path);
-            let points = models_cv::gltf::load_vertex_positions(&document,&buffers);
+            let points = models_cv::gltf::load_vertex_positions(&document,&buffers).expect("Unsupported position data");
             render_points(&points);
         } else if path.ends_with(".obj") {
             let model = models_cv::obj::load(&path);
@@ -23,8 +23,8 @@ fn main() {
     }
 }
 
-fn render_points(points: &Vec<Vec<Vector3<f32>>>) -> () {
-    let scene_capacity: usize = points.iter().map(|vec| vec.iter().map(|ps| ps.len())).flatten().sum();
+fn render_points(points: &[Vec<Vector3<f32>>]) {
+    let scene_capacity: usize = points.iter().flat_map(|vec| vec.iter().map(|ps| ps.len())).sum();
 
     let mut window = Window::new("Gltf Model");
     let mut scene_nodes = Vec::<kiss3d::scene::SceneNode>::with_capacity(scene_capacity);
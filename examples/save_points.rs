@@ -1,128 +1,77 @@
 extern crate nalgebra as na;
 
-use std::path::Path;
-use std::fs::File;
-use std::io::BufWriter;
-use std::result::Result;
-use png::EncodingError;
-use models_cv::io::{serialize_feature_matches,serialize_landmarks,deserialize_feature_matches,deserialize_landmarks};
-use na::{Vector3,Isometry3,Point3, Matrix3};
+use std::collections::BTreeMap;
+use models_cv::camera::{Camera,Resolution};
+use models_cv::convention::Convention;
+use models_cv::intrinsics::{IntrinsicsBuilder,FocalLength};
+use models_cv::dataset::Dataset;
+use models_cv::framing::{Framing,BoundingSphere};
+use models_cv::trajectory;
+use models_cv::io::Format;
+use models_cv::schema::{MeshInfo,GenerationParameters};
+use na::Vector3;
 
 
 fn main() {
     if let Some(path) = std::env::args().nth(1) {
+        let output_dir = std::env::args().nth(2).unwrap_or("output".to_string());
+        let convention = match std::env::args().nth(3).as_deref() {
+            Some("opencv") => Convention::OpenCv,
+            _ => Convention::OpenGl
+        };
         if path.ends_with(".gltf") {
             let (document, buffers) = models_cv::gltf::load(&path);
             let names = models_cv::gltf::load_mesh_names(&document);
-            let points = models_cv::gltf::load_vertex_positions(&document,&buffers);
-            project_points(&points[0],&names[0]);
+            let points = models_cv::gltf::load_vertex_positions(&document,&buffers).expect("Unsupported position data");
+            project_points(&points[0],&names[0],&path,&output_dir,convention);
         } else if path.ends_with(".obj") {
-            let splits = path.split(|c| c == '/' || c == '.').collect::<Vec<_>>();
+            let splits = path.split(['/', '.']).collect::<Vec<_>>();
             let name = splits[splits.len()-2].to_string();
             let model = models_cv::obj::load(&path);
             let points = models_cv::obj::load_vertex_positions(&model);
-            project_points(&points[0],&name);
+            project_points(&points[0],&name,&path,&output_dir,convention);
         }
+    } else {
+        println!("usage: save_points <FILE> [OUTPUT_DIR] [opengl|opencv]");
     }
 }
 
-fn project_points(points: &Vec<Vector3<f32>>, mesh_name: &String) -> () {
-    let scene_capacity: usize = points.len();
-
-    let mut scene_center = Point3::<f32>::new(0.0, 0.0, 0.0);
-
-    for point in points {
-        scene_center += point;
-    } 
-
-    scene_center *= 1.0/scene_capacity as f32;
-    
-    // X Translation
-    // let look_ats = vec![Point3::new(scene_center.x+0.3,scene_center.y,scene_center.z),Point3::new(scene_center.x,scene_center.y,scene_center.z),Point3::new(scene_center.x-0.4,scene_center.y,scene_center.z)];
-    // let camera_trajectories = vec![Point3::new(0.3,0.0,6.2),Point3::new(0.0,0.0,6.2),Point3::new(-0.4,0.0,6.2)];
-
-    // Y Translation
-    let look_ats = vec![Point3::new(scene_center.x,scene_center.y+0.2,scene_center.z),Point3::new(scene_center.x,scene_center.y,scene_center.z),Point3::new(scene_center.x,scene_center.y-0.3,scene_center.z)];
-    let camera_trajectories = vec![Point3::new(0.0,0.2,7.2),Point3::new(0.0,0.0,7.2),Point3::new(0.0,-0.3,7.2)];
-
-    // Z Translation
-    // let look_ats = vec![Point3::new(scene_center.x,scene_center.y,scene_center.z+0.4),Point3::new(scene_center.x,scene_center.y,scene_center.z),Point3::new(scene_center.x,scene_center.y,scene_center.z-0.6)];
-    // let camera_trajectories = vec![Point3::new(0.0,0.0,6.2+0.4),Point3::new(0.0,0.0,6.2),Point3::new(0.0,0.0,6.2-0.6)];
-
-    // let camera_trajectories = models_cv::generate_camera_trajectory(&Point3::new(-0.3,0.0,7.2), &scene_center, 60.0, 10);
-    // let look_ats = vec![Point3::new(scene_center.x,scene_center.y,scene_center.z);camera_trajectories.len()];
-    
-    let view_matrices = camera_trajectories.iter().zip(look_ats).map(|(eye,at)| {
-        let view_matrix = Isometry3::look_at_rh(&eye, &at, &Vector3::y_axis()).to_matrix();
-        view_matrix.fixed_view::<3,4>(0, 0).into_owned()
+fn project_points(points: &[Vector3<f64>], mesh_name: &String, mesh_path: &str, output_dir: &str, convention: Convention) {
+    let camera_description = IntrinsicsBuilder::new(Resolution::new(640, 480), FocalLength::HorizontalFov(35.5f64.to_radians()))
+        .convention(Convention::OpenGl)
+        .build()
+        .expect("Invalid intrinsics");
+    let framing = Framing::for_camera(points, &camera_description, 0.8).expect("Camera has no field of view");
+    let radius = BoundingSphere::from_points(points).get_radius();
+    let target = *framing.get_target();
+    let eye = framing.eye_along(&Vector3::z());
+
+    // Y Translation, relative to the model size
+    let offsets = [0.2, 0.0, -0.3].map(|y| Vector3::new(0.0, y*radius, 0.0));
+    let poses = offsets.iter().map(|offset| trajectory::look_at(&(eye + offset), &(target + offset), &Vector3::y())).collect::<Vec<_>>();
+
+    // Orbit around the model
+    // let poses = framing.reframe(&trajectory::orbit(&target, radius, 0.0, 0.0, std::f64::consts::PI/3.0, 10, &Vector3::y_axis()));
+
+    let cameras = poses.into_iter().enumerate().map(|(id,pose)| {
+        Camera::new(id, &format!("camera_{}",id), pose, camera_description)
     }).collect::<Vec<_>>();
-    let screen_width = 640.0;
-    let screen_height = 480.0;
-    let f = -1000.0; 
-    let cx = screen_width/2.0;
-    let cy = screen_height/2.0;
-    let intrinsic_matrix = Matrix3::<f32>::new(
-        f,0.0,cx,
-        0.0,f,cy,
-        0.0,0.0,1.0);
-    let intrinsic_matrices = vec![intrinsic_matrix;view_matrices.len()];
     let indexed_landmarks = models_cv::generate_landmarks(&points.iter().enumerate().map(|(i,c)| (i,*c)).collect::<Vec<_>>());
 
     let visible_screen_points_with_idx 
         = models_cv::filter_screen_points_for_camera_views(
             &indexed_landmarks,
-            &intrinsic_matrix,
-            &view_matrices,
-            screen_width,
-            screen_height,
+            &cameras,
             models_cv::filter::FilterType::Rasterizer
         );
 
-    let camera_features = models_cv::generate_matches(&view_matrices,&intrinsic_matrices, &visible_screen_points_with_idx);
-
-
-    let cam_features_path = format!("/home/marc/Workspace/Rust/models-cv/output/camera_features_{}.yaml",mesh_name);
-    let landmarks_path = format!("/home/marc/Workspace/Rust/models-cv/output/landmarks_{}.yaml",mesh_name);
-    serialize_feature_matches(&cam_features_path, &camera_features).expect("Serialzing failed");
-    serialize_landmarks(&landmarks_path, &indexed_landmarks).expect("Serialzing failed");
-    let loaded_data_features = deserialize_feature_matches(&cam_features_path);
-    let loaded_data_landmarks = deserialize_landmarks(&landmarks_path);
-    assert_eq!(camera_features,loaded_data_features);
-    assert_eq!(indexed_landmarks,loaded_data_landmarks);
+    let camera_features = models_cv::generate_matches(&cameras, &visible_screen_points_with_idx);
 
-    for (camera_id, visible_points_for_cam) in visible_screen_points_with_idx.iter().enumerate() {
-        let visible_screen_points = visible_points_for_cam.iter().map(|&(_,v)| v).collect::<Vec<_>>();
-        let data_vec = models_cv::io::calculate_rgb_byte_vec(&visible_screen_points, screen_width as usize, screen_height as usize);
-        let name = format!("/home/marc/Workspace/Rust/models-cv/output/camera_features_{}_{}.png",mesh_name,camera_id+1);
-        write_png_data_to_file(name.as_str(), &data_vec,screen_width as u32, screen_height as u32).expect("Writing png failed!");
-        //write_test_png();
-    }
-
-
-}
-
-fn write_png_data_to_file(path_str: &str, data_vec: &Vec<u8>, screen_width: u32, screen_height: u32) -> Result<(),EncodingError> {
-    let path = Path::new(path_str);
-    let file = File::create(path).unwrap();
-    let ref mut w = BufWriter::new(file);
-
-    let mut encoder = png::Encoder::new(w, screen_width, screen_height); 
-    encoder.set_color(png::ColorType::Rgb);
-    encoder.set_depth(png::BitDepth::Eight);
-    encoder.set_source_gamma(png::ScaledFloat::new(1.0 / 2.2));     // 1.0 / 2.2, unscaled, but rounded
-    let source_chromaticities = png::SourceChromaticities::new(     // Using unscaled instantiation here
-        (0.31270, 0.32900),
-        (0.64000, 0.33000),
-        (0.30000, 0.60000),
-        (0.15000, 0.06000)
-    );
-    encoder.set_source_chromaticities(source_chromaticities);
-    let mut writer = encoder.write_header().unwrap();
-
-    writer.write_image_data(&data_vec[..]) // Save
+    let source_mesh = MeshInfo {name: mesh_name.clone(), path: Some(mesh_path.to_string()), vertex_count: points.len()};
+    let generation = GenerationParameters {filter_type: models_cv::filter::FilterType::Rasterizer, extras: BTreeMap::from([("trajectory".to_string(),"y_translation".to_string())])};
+    let dataset = Dataset::new(indexed_landmarks, camera_features, source_mesh, generation).to_convention(convention).expect("Conversion failed");
+    let dataset_dir = format!("{}/{}",output_dir,mesh_name);
+    dataset.save(&dataset_dir, Format::Yaml, true).expect("Saving dataset failed");
+    let loaded_dataset = Dataset::load(&dataset_dir).expect("Loading dataset failed");
+    assert_eq!(dataset,loaded_dataset);
 }
-
-fn write_test_png() -> () {
-    let data_vec = vec![255, 0, 0, 0, 0, 0, 0, 255 ,0, 0,0,255]; // An array containing a RGB sequence. First pixel is red and second pixel is black.
-    write_png_data_to_file("/home/marc/Workspace/Rust/models-cv/output/test.png",&data_vec,2,2).unwrap();
-}
\ No newline at end of file
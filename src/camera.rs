@@ -0,0 +1,101 @@
+extern crate nalgebra as na;
+
+use na::{Vector2,Isometry3,Matrix3x4};
+use serde::{Serialize, Deserialize};
+use crate::float::Float;
+use crate::camera_model::CameraModel;
+use crate::rig::RigMembership;
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Serialize,Deserialize)]
+pub struct Resolution {
+    width: usize,
+    height: usize
+}
+
+impl Resolution {
+    pub fn new(width: usize, height: usize) -> Resolution {
+        Resolution {width, height}
+    }
+    pub fn get_width(&self) -> usize {self.width}
+    pub fn get_height(&self) -> usize {self.height}
+}
+
+/**
+ * Camera model and resolution of a single view
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct CameraDescription<F: Float, C: CameraModel<F>> {
+    camera_model: C,
+    resolution: Resolution,
+    _scalar: std::marker::PhantomData<F>
+}
+
+impl<F: Float, C: CameraModel<F>> CameraDescription<F,C> {
+    pub fn new(camera_model: C, resolution: Resolution) -> CameraDescription<F,C> {
+        CameraDescription {camera_model, resolution, _scalar: std::marker::PhantomData}
+    }
+    pub fn get_camera_model(&self) -> &C {&self.camera_model}
+    pub fn get_resolution(&self) -> Resolution {self.resolution}
+
+    /**
+     * True if the pixel lies within the image bounds
+     */
+    pub fn contains_pixel(&self, pixel: &Vector2<usize>) -> bool {
+        pixel.x < self.resolution.get_width() && pixel.y < self.resolution.get_height()
+    }
+}
+
+/**
+ * A single view: a camera description placed in the world.
+ * The pose transforms from the camera frame to the world frame.
+ */
+#[derive(Debug,Clone,PartialEq)]
+pub struct Camera<F: Float, C: CameraModel<F>> {
+    id: usize,
+    name: String,
+    pose: Isometry3<F>,
+    description: CameraDescription<F,C>,
+    timestamp: Option<F>,
+    rig: Option<RigMembership<F>>
+}
+
+impl<F: Float, C: CameraModel<F>> Camera<F,C> {
+    pub fn new(id: usize, name: &str, pose: Isometry3<F>, description: CameraDescription<F,C>) -> Camera<F,C> {
+        Camera {id, name: name.to_string(), pose, description, timestamp: None, rig: None}
+    }
+
+    /**
+     * Places the camera with a view (world to camera) transform
+     */
+    pub fn from_view(id: usize, name: &str, view: &Isometry3<F>, description: CameraDescription<F,C>) -> Camera<F,C> {
+        Camera::new(id, name, view.inverse(), description)
+    }
+
+    pub fn with_timestamp(self, timestamp: Option<F>) -> Camera<F,C> {
+        Camera {timestamp, ..self}
+    }
+
+    pub fn with_rig(self, rig: Option<RigMembership<F>>) -> Camera<F,C> {
+        Camera {rig, ..self}
+    }
+
+    pub fn get_id(&self) -> usize {self.id}
+    pub fn get_name(&self) -> &str {&self.name}
+    pub fn get_pose(&self) -> &Isometry3<F> {&self.pose}
+    pub fn get_description(&self) -> &CameraDescription<F,C> {&self.description}
+    pub fn get_camera_model(&self) -> &C {self.description.get_camera_model()}
+    pub fn get_resolution(&self) -> Resolution {self.description.get_resolution()}
+    pub fn get_timestamp(&self) -> Option<F> {self.timestamp}
+    pub fn get_rig(&self) -> Option<&RigMembership<F>> {self.rig.as_ref()}
+
+    pub fn get_view(&self) -> Isometry3<F> {
+        self.pose.inverse()
+    }
+
+    /**
+     * World to camera transform as a 3x4 matrix
+     */
+    pub fn get_view_matrix(&self) -> Matrix3x4<F> {
+        self.get_view().to_matrix().fixed_view::<3,4>(0,0).into_owned()
+    }
+}
@@ -1,102 +1,318 @@
 extern crate nalgebra as na;
 
-use na::{Vector2,Matrix3x4,Matrix3};
+use na::{Vector2,Vector3,Matrix3x4,Matrix3,Matrix4,Isometry3};
 use std::collections::HashMap;
+use crate::camera::{Camera,Resolution};
+use crate::feature::Feature;
+use crate::float::{self,Float};
+use crate::camera_model::{CameraModel,AnyCameraModel,Pinhole};
+use crate::convention::Convention;
+use crate::rig::RigMembership;
+use crate::schema::{CameraFeaturesDocument,CameraFeaturesSerial,FeatureSerial,RigMembershipSerial,SCHEMA_VERSION};
 
-#[derive(Debug,PartialEq)]
-pub struct CameraFeatures {
-    // The first entry of the tuple is the global point id
-    feature_map: HashMap<usize,Vector2<usize>>,
+#[derive(Debug,Clone,PartialEq)]
+pub struct CameraFeatures<F: Float> {
+    // The key is the global point id
+    feature_map: HashMap<usize,Feature<F>>,
     cam_id: usize,
-    view_matrix: Matrix3x4<f32>,
-    intrinsic_matrix: Matrix3<f32>
+    view_matrix: Matrix3x4<F>,
+    camera_model: String,
+    camera_parameters: Vec<F>,
+    convention: Convention,
+    rig: Option<RigMembership<F>>,
+    timestamp: Option<F>,
+    // Files written before the schema was versioned do not carry a resolution
+    resolution: Option<Resolution>
 }
 
-impl CameraFeatures {
-    pub fn new(match_map: HashMap<usize,Vector2<usize>>, cam_id: usize, view_matrix: Matrix3x4<f32>,intrinsic_matrix: Matrix3<f32>) -> CameraFeatures {
+impl<F: Float> CameraFeatures<F> {
+    pub fn new<C: CameraModel<F>>(match_map: HashMap<usize,Feature<F>>, cam_id: usize, view_matrix: Matrix3x4<F>, camera_model: &C, resolution: Resolution) -> CameraFeatures<F> {
         CameraFeatures {
             feature_map: match_map,
             cam_id,
             view_matrix,
-            intrinsic_matrix
+            camera_model: camera_model.name().to_string(),
+            camera_parameters: camera_model.get_parameters(),
+            convention: camera_model.convention(),
+            rig: None,
+            timestamp: None,
+            resolution: Some(resolution)
         }
     }
-    pub fn get_feature_map(&self) -> &HashMap<usize,Vector2<usize>> {&self.feature_map}
+
+    /**
+     * Takes the id, view, model, resolution, rig membership and timestamp from the camera
+     */
+    pub fn from_camera<C: CameraModel<F>>(match_map: HashMap<usize,Feature<F>>, camera: &Camera<F,C>) -> CameraFeatures<F> {
+        CameraFeatures::new(match_map, camera.get_id(), camera.get_view_matrix(), camera.get_camera_model(), camera.get_resolution())
+            .with_rig(camera.get_rig().copied())
+            .with_timestamp(camera.get_timestamp())
+    }
+
+    pub fn with_rig(self, rig: Option<RigMembership<F>>) -> CameraFeatures<F> {
+        CameraFeatures {rig, ..self}
+    }
+
+    pub fn with_view_matrix(self, view_matrix: Matrix3x4<F>) -> CameraFeatures<F> {
+        CameraFeatures {view_matrix, ..self}
+    }
+
+    /**
+     * Replaces the camera model. The features are kept as they are
+     */
+    pub fn with_camera_model<C: CameraModel<F>>(self, camera_model: &C) -> CameraFeatures<F> {
+        CameraFeatures {camera_model: camera_model.name().to_string(), camera_parameters: camera_model.get_parameters(), convention: camera_model.convention(), ..self}
+    }
+
+    /**
+     * Capture time of the view. Features without their own timestamp were captured at this time
+     */
+    pub fn with_timestamp(self, timestamp: Option<F>) -> CameraFeatures<F> {
+        CameraFeatures {timestamp, ..self}
+    }
+
+    pub fn get_feature_map(&self) -> &HashMap<usize,Feature<F>> {&self.feature_map}
     pub fn get_cam_id(&self) -> usize {self.cam_id}
-    pub fn get_view_matrix(&self) ->  Matrix3x4<f32> {self.view_matrix}
-    pub fn get_intrinsic_matrix(&self) ->  Matrix3<f32> {self.intrinsic_matrix}
-    pub fn to_serial(fm_vec: &Vec<CameraFeatures>) -> Vec<(usize, [f32;12],[f32;9], Vec<(usize,(usize,usize))>)> {
-        fm_vec.into_iter().map(|fm|{
-            let mut map_vec =  Vec::<(usize,(usize,usize))>::with_capacity(fm.feature_map.len());
-
-            for (k,v) in &fm.feature_map {
-                map_vec.push((*k,(v.x,v.y)));
-            }
-    
-            (fm.cam_id, [
-                fm.view_matrix[(0,0)],
-                fm.view_matrix[(0,1)],
-                fm.view_matrix[(0,2)],
-                fm.view_matrix[(0,3)],
-                fm.view_matrix[(1,0)],
-                fm.view_matrix[(1,1)],
-                fm.view_matrix[(1,2)],
-                fm.view_matrix[(1,3)],
-                fm.view_matrix[(2,0)],
-                fm.view_matrix[(2,1)],
-                fm.view_matrix[(2,2)],
-                fm.view_matrix[(2,3)],
-                ],
-                [
-                fm.intrinsic_matrix[(0,0)],
-                fm.intrinsic_matrix[(0,1)],
-                fm.intrinsic_matrix[(0,2)],
-                fm.intrinsic_matrix[(1,0)],
-                fm.intrinsic_matrix[(1,1)],
-                fm.intrinsic_matrix[(1,2)],
-                fm.intrinsic_matrix[(2,0)],
-                fm.intrinsic_matrix[(2,1)],
-                fm.intrinsic_matrix[(2,2)]
-                ],
-                map_vec
-            )
-        }).collect::<Vec<_>>()
-
-    }
-
-    pub fn from_serial(serial: &Vec<(usize, [f32;12], [f32;9], Vec<(usize,(usize,usize))>)>) -> Vec<CameraFeatures> {
-        serial.into_iter().map(|s| {
-            let cam_id = s.0;
-            let view_arr = &s.1;
-            let intrinsic_arr = &s.2;
-            let vec = &s.3;
-
-            let view_matrix = Matrix3x4::<f32>::new(
-                view_arr[0],view_arr[1],view_arr[2],view_arr[3],
-                view_arr[4],view_arr[5],view_arr[6],view_arr[7],
-                view_arr[8],view_arr[9],view_arr[10],view_arr[11],
-            );
-            let intrinsic_matrix = Matrix3::<f32>::new(
-                intrinsic_arr[0],intrinsic_arr[1],intrinsic_arr[2],
-                intrinsic_arr[3],intrinsic_arr[4],intrinsic_arr[5],
-                intrinsic_arr[6],intrinsic_arr[7],intrinsic_arr[8],
-            );
-
-            let mut feature_map = HashMap::<usize,Vector2<usize>>::with_capacity(vec.len());
-            for &(point_id,(x,y)) in vec {
-                feature_map.insert(point_id, Vector2::new(x,y));
-            }
-    
-            CameraFeatures {
-                feature_map,
-                cam_id,
-                view_matrix,
-                intrinsic_matrix
-            }
-    
-        }).collect::<Vec<_>>()
+    pub fn get_view_matrix(&self) ->  Matrix3x4<F> {self.view_matrix}
+    pub fn get_resolution(&self) -> Option<Resolution> {self.resolution}
+    pub fn get_camera_model_name(&self) -> &str {&self.camera_model}
+    pub fn get_camera_parameters(&self) -> &Vec<F> {&self.camera_parameters}
+    pub fn get_convention(&self) -> Convention {self.convention}
+    pub fn get_rig(&self) -> Option<&RigMembership<F>> {self.rig.as_ref()}
+    pub fn get_timestamp(&self) -> Option<F> {self.timestamp}
+
+    /**
+     * Camera to world transform
+     */
+    pub fn get_pose(&self) -> Isometry3<F> {
+        to_isometry(&self.view_matrix).expect("View matrix has to be a rigid transform").inverse()
+    }
+
+    /**
+     * Reconstructs the camera model. Returns None if the stored model is not a C.
+     * Use AnyCameraModel if the model is not known in advance.
+     */
+    pub fn get_camera_model<C: CameraModel<F>>(&self) -> Option<C> {
+        C::from_parameters(&self.camera_model, &self.camera_parameters)
+    }
+
+    /**
+     * The same camera expressed in the target convention: the view matrix and bearings are rotated by PI around X
+     * and the image rows of the model and the features are flipped. Landmarks are not affected.
+     * Returns None if the resolution or the camera model is unknown.
+     */
+    pub fn to_convention(&self, target: Convention) -> Option<CameraFeatures<F>> {
+        if self.convention == target {
+            return Some(self.clone());
+        }
+        let resolution = self.resolution?;
+        let height = resolution.get_height();
+        let image_height = float::from_usize::<F>(height);
+        let camera_model = self.get_camera_model::<AnyCameraModel<F>>()?.flip_convention(image_height);
+        let feature_map = self.feature_map.iter().map(|(&id,f)| {
+            let (position, pixel) = (f.get_position(), f.get_pixel());
+            let feature = Feature::from_parts(&Vector2::new(position.x, image_height - position.y), &Vector2::new(pixel.x, (height-1).saturating_sub(pixel.y)))
+                .with_bearing(f.get_bearing().map(|b| self.convention.convert_camera_vector(b, target)))
+                .with_timestamp(f.get_timestamp());
+            (id, feature)
+        }).collect::<HashMap<usize,Feature<F>>>();
+        Some(CameraFeatures {
+            feature_map,
+            cam_id: self.cam_id,
+            view_matrix: self.convention.convert_view_matrix(&self.view_matrix, target),
+            camera_model: self.camera_model.clone(),
+            camera_parameters: camera_model.get_parameters(),
+            convention: target,
+            rig: self.rig.map(|rig| RigMembership::new(rig.get_rig_id(), rig.get_sensor_id(), rig.get_frame_id(), self.convention.convert_camera_pose(rig.get_sensor_to_body(), target))),
+            timestamp: self.timestamp,
+            resolution: self.resolution
+        })
+    }
+
+    pub fn to_camera_serial(&self) -> CameraFeaturesSerial<F> {
+        let mut features = self.feature_map.iter().map(|(&landmark_id,f)| {
+            let (position, pixel) = (f.get_position(), f.get_pixel());
+            let bearing = f.get_bearing().map(|b| [b.x,b.y,b.z]);
+            FeatureSerial {landmark_id, x: position.x, y: position.y, pixel: Some([pixel.x,pixel.y]), bearing, timestamp: f.get_timestamp()}
+        }).collect::<Vec<_>>();
+        features.sort_unstable_by_key(|f| f.landmark_id);
+
+        let mut view_matrix = [F::zero();12];
+        // nalgebra is column major, the serialized matrices are row major
+        view_matrix.copy_from_slice(self.view_matrix.transpose().as_slice());
+
+        CameraFeaturesSerial {
+            cam_id: self.cam_id,
+            camera_model: self.camera_model.clone(),
+            camera_parameters: self.camera_parameters.clone(),
+            resolution: self.resolution,
+            convention: Some(self.convention),
+            view_matrix,
+            rig: self.rig.map(|rig| {
+                let mut sensor_to_body = [F::zero();12];
+                sensor_to_body.copy_from_slice(rig.get_sensor_to_body().to_matrix().fixed_view::<3,4>(0,0).transpose().as_slice());
+                RigMembershipSerial {rig_id: rig.get_rig_id(), sensor_id: rig.get_sensor_id(), frame_id: rig.get_frame_id(), sensor_to_body}
+            }),
+            timestamp: self.timestamp,
+            intrinsic_matrix: None,
+            features
+        }
+    }
+
+    /**
+     * Panics if the view matrix or the rig extrinsics are not rigid transforms, see try_from_camera_serial
+     */
+    pub fn from_camera_serial(serial: &CameraFeaturesSerial<F>) -> CameraFeatures<F> {
+        Self::try_from_camera_serial(serial).expect("Camera has to have a rigid view matrix and rig extrinsics")
+    }
+
+    /**
+     * InvalidData if the view matrix or the rig extrinsics are not rigid transforms
+     */
+    pub fn try_from_camera_serial(serial: &CameraFeaturesSerial<F>) -> std::io::Result<CameraFeatures<F>> {
+        let view_matrix = Matrix3x4::<F>::from_row_slice(&serial.view_matrix);
+        to_isometry(&view_matrix).ok_or_else(|| invalid_data(format!("View matrix of camera {} is not a rigid transform", serial.cam_id)))?;
+        // Files before version 3 only store the pinhole matrix
+        let camera_parameters = match (&serial.intrinsic_matrix, serial.camera_parameters.is_empty()) {
+            (Some(intrinsic_arr), true) => Pinhole::new(Matrix3::<F>::from_row_slice(intrinsic_arr)).get_parameters(),
+            _ => serial.camera_parameters.clone()
+        };
+        let feature_map = serial.features.iter().map(|f| {
+            let position = Vector2::new(f.x,f.y);
+            let feature = match f.pixel {
+                Some([x,y]) => Feature::from_parts(&position, &Vector2::new(x,y)),
+                None => Feature::new(&position)
+            };
+            (f.landmark_id, feature.with_bearing(f.bearing.map(|[x,y,z]| Vector3::new(x,y,z))).with_timestamp(f.timestamp))
+        }).collect::<HashMap<usize,Feature<F>>>();
+
+        let convention = serial.convention
+            .or_else(|| AnyCameraModel::<F>::from_parameters(&serial.camera_model, &camera_parameters).map(|m| m.convention()))
+            .unwrap_or_default();
+
+        let rig = match &serial.rig {
+            Some(rig) => {
+                let sensor_to_body = to_isometry(&Matrix3x4::from_row_slice(&rig.sensor_to_body))
+                    .ok_or_else(|| invalid_data(format!("Rig extrinsics of camera {} are not a rigid transform", serial.cam_id)))?;
+                Some(RigMembership::new(rig.rig_id, rig.sensor_id, rig.frame_id, sensor_to_body))
+            },
+            None => None
+        };
 
+        Ok(CameraFeatures {
+            feature_map,
+            cam_id: serial.cam_id,
+            view_matrix,
+            camera_model: serial.camera_model.clone(),
+            camera_parameters,
+            convention,
+            rig,
+            timestamp: serial.timestamp,
+            resolution: serial.resolution
+        })
+    }
+
+    pub fn to_serial(fm_vec: &[CameraFeatures<F>]) -> CameraFeaturesDocument<F> {
+        let cameras = fm_vec.iter().map(|fm| fm.to_camera_serial()).collect::<Vec<_>>();
+        CameraFeaturesDocument {version: SCHEMA_VERSION, cameras}
+    }
+
+    pub fn from_serial(serial: &CameraFeaturesDocument<F>) -> Vec<CameraFeatures<F>> {
+        serial.cameras.iter().map(CameraFeatures::from_camera_serial).collect::<Vec<_>>()
+    }
+
+    /**
+     * InvalidData if a camera is malformed, see try_from_camera_serial
+     */
+    pub fn try_from_serial(serial: &CameraFeaturesDocument<F>) -> std::io::Result<Vec<CameraFeatures<F>>> {
+        serial.cameras.iter().map(CameraFeatures::try_from_camera_serial).collect()
     }
 }
 
+/**
+ * None if the 3x4 matrix is not a rigid transform
+ */
+fn to_isometry<F: Float>(matrix: &Matrix3x4<F>) -> Option<Isometry3<F>> {
+    let mut homogeneous = Matrix4::<F>::identity();
+    homogeneous.fixed_view_mut::<3,4>(0,0).copy_from(matrix);
+    na::try_convert::<Matrix4<F>,Isometry3<F>>(homogeneous)
+}
 
+fn invalid_data(message: String) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use na::{Translation3,UnitQuaternion};
+    use crate::camera::CameraDescription;
+    use crate::camera_model::{KannalaBrandt,Equirectangular};
+    use crate::filter::FilterType;
+    use crate::landmark::Landmark;
+    use crate::generate_camera_features_iter;
+
+    const HEIGHT: usize = 480;
+
+    fn landmarks() -> Vec<Landmark<f64>> {
+        (0..64).map(|i| Landmark::new(&i, &Vector3::new((i%8) as f64*0.4 - 1.4, (i/8) as f64*0.35 - 1.2, (i%3) as f64*0.5 - 0.5))).collect()
+    }
+
+    fn pose() -> Isometry3<f64> {
+        Isometry3::from_parts(Translation3::new(0.3, -0.2, 4.0), UnitQuaternion::from_euler_angles(0.05, -0.1, 0.2))
+    }
+
+    fn models() -> Vec<AnyCameraModel<f64>> {
+        vec![
+            AnyCameraModel::Pinhole(Pinhole::from_focal_and_principal_point(-300.0, -300.0, 320.0, 240.0, 0.0)),
+            AnyCameraModel::KannalaBrandt(KannalaBrandt::new(-250.0, -250.0, 320.0, 240.0, [0.01, -0.005, 0.001, 0.0])),
+            AnyCameraModel::Equirectangular(Equirectangular::full_sphere(960, HEIGHT).flip_convention(HEIGHT as f64))
+        ]
+    }
+
+    fn camera_features(model: AnyCameraModel<f64>, pose: Isometry3<f64>) -> CameraFeatures<f64> {
+        let cameras = vec![Camera::new(0, "camera", pose, CameraDescription::new(model, Resolution::new(640, HEIGHT)))];
+        let landmarks = landmarks();
+        let camera_features = generate_camera_features_iter(&landmarks, &cameras, FilterType::Depth).next().unwrap();
+        camera_features
+    }
+
+    fn assert_features_eq(a: &CameraFeatures<f64>, b: &CameraFeatures<f64>) {
+        assert_eq!(a.get_convention(), b.get_convention());
+        assert!((a.get_view_matrix() - b.get_view_matrix()).norm() < 1e-12);
+        assert_eq!(a.get_camera_model_name(), b.get_camera_model_name());
+        assert_eq!(a.get_camera_parameters(), b.get_camera_parameters());
+        let mut ids = a.get_feature_map().keys().collect::<Vec<_>>();
+        ids.sort();
+        let mut other_ids = b.get_feature_map().keys().collect::<Vec<_>>();
+        other_ids.sort();
+        assert_eq!(ids, other_ids);
+        for (id,feature) in a.get_feature_map() {
+            let other = &b.get_feature_map()[id];
+            assert!((feature.get_position() - other.get_position()).norm() < 1e-9);
+            assert!((feature.get_bearing().unwrap() - other.get_bearing().unwrap()).norm() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn opencv_features_match_a_flipped_camera() {
+        for model in models() {
+            let opengl = camera_features(model, pose());
+            assert_eq!(opengl.get_convention(), Convention::OpenGl);
+            assert!(opengl.get_feature_map().len() > 32);
+            let flipped_pose = Convention::OpenGl.convert_camera_pose(&pose(), Convention::OpenCv);
+            let opencv = camera_features(model.flip_convention(HEIGHT as f64), flipped_pose);
+            assert_features_eq(&opengl.to_convention(Convention::OpenCv).unwrap(), &opencv);
+        }
+    }
+
+    #[test]
+    fn convention_round_trip() {
+        for model in models() {
+            let opengl = camera_features(model, pose());
+            let round_trip = opengl.to_convention(Convention::OpenCv).unwrap().to_convention(Convention::OpenGl).unwrap();
+            assert_features_eq(&round_trip, &opengl);
+            assert_eq!(round_trip.get_pose(), opengl.get_pose());
+        }
+    }
+}
@@ -0,0 +1,112 @@
+extern crate nalgebra as na;
+
+use na::{Vector2,Vector3,Matrix2x3,Matrix2x4};
+use crate::float::Float;
+use crate::convention::Convention;
+use super::{CameraModel,DepthInterpolation};
+
+pub const NAME: &str = "affine";
+
+/**
+ * General affine camera [u,v]^T = M*[x,y,z,1]^T.
+ * Projection rays are parallel to the cross product of the first two rows of M,
+ * the forward sign selects which way along the rays the camera looks.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Affine<F: Float> {
+    matrix: Matrix2x4<F>,
+    forward_sign: F,
+    viewing_direction: Vector3<F>
+}
+
+impl<F: Float> Affine<F> {
+    pub fn new(matrix: Matrix2x4<F>, forward_sign: F) -> Affine<F> {
+        let row_0 = Vector3::new(matrix[(0,0)],matrix[(0,1)],matrix[(0,2)]);
+        let row_1 = Vector3::new(matrix[(1,0)],matrix[(1,1)],matrix[(1,2)]);
+        let viewing_direction = row_0.cross(&row_1).try_normalize(F::zero()).expect("Affine matrix has to have rank 2")*forward_sign.signum();
+        Affine {matrix, forward_sign: forward_sign.signum(), viewing_direction}
+    }
+
+    pub fn get_matrix(&self) -> &Matrix2x4<F> {&self.matrix}
+    pub fn get_viewing_direction(&self) -> &Vector3<F> {&self.viewing_direction}
+}
+
+impl<F: Float> CameraModel<F> for Affine<F> {
+    fn name(&self) -> &'static str {NAME}
+
+    fn project(&self, p_cam: &Vector3<F>) -> Option<Vector2<F>> {
+        Some(self.matrix*p_cam.push(F::one()))
+    }
+
+    /**
+     * All pixels share the viewing direction
+     */
+    fn unproject(&self, _p_image: &Vector2<F>) -> Option<Vector3<F>> {
+        Some(self.viewing_direction)
+    }
+
+    fn jacobian(&self, _p_cam: &Vector3<F>) -> Matrix2x3<F> {
+        self.matrix.fixed_view::<2,3>(0,0).into_owned()
+    }
+
+    /**
+     * Signed distance along the viewing direction
+     */
+    fn depth(&self, p_cam: &Vector3<F>) -> F {
+        self.viewing_direction.dot(p_cam)
+    }
+
+    fn depth_interpolation(&self) -> DepthInterpolation {
+        DepthInterpolation::Affine
+    }
+
+    /**
+     * OpenGL if the viewing direction points along -Z
+     */
+    fn convention(&self) -> Convention {
+        Convention::from_forward_sign(self.viewing_direction.z)
+    }
+
+    /**
+     * The columns of y and z change sign and the second row is mirrored within the image height
+     */
+    fn flip_convention(&self, image_height: F) -> Affine<F> {
+        let mut matrix = self.matrix;
+        matrix.column_mut(1).neg_mut();
+        matrix.column_mut(2).neg_mut();
+        matrix.row_mut(1).neg_mut();
+        matrix[(1,3)] += image_height;
+        Affine::new(matrix, -self.forward_sign)
+    }
+
+    /**
+     * Row major M followed by the forward sign
+     */
+    fn get_parameters(&self) -> Vec<F> {
+        let mut parameters = self.matrix.transpose().as_slice().to_vec();
+        parameters.push(self.forward_sign);
+        parameters
+    }
+
+    /**
+     * The general affine map has no separate focal length and principal point
+     */
+    fn intrinsic_indices(&self) -> Option<([usize;2],[usize;2])> {
+        None
+    }
+
+    fn from_parameters(name: &str, parameters: &[F]) -> Option<Affine<F>> {
+        match (name, parameters) {
+            (NAME, &[.., forward_sign]) if parameters.len() == 9 => {
+                let matrix = Matrix2x4::from_row_slice(&parameters[..8]);
+                let row_0 = Vector3::new(matrix[(0,0)],matrix[(0,1)],matrix[(0,2)]);
+                let row_1 = Vector3::new(matrix[(1,0)],matrix[(1,1)],matrix[(1,2)]);
+                match row_0.cross(&row_1).norm() > F::zero() {
+                    true => Some(Affine::new(matrix, forward_sign)),
+                    false => None
+                }
+            },
+            _ => None
+        }
+    }
+}
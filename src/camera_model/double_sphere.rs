@@ -0,0 +1,123 @@
+extern crate nalgebra as na;
+
+use na::{Vector2,Vector3,Matrix2x3};
+use crate::float::{self,Float};
+use crate::convention::Convention;
+use super::{CameraModel,mirror_z};
+
+pub const NAME: &str = "double_sphere";
+
+/**
+ * Double Sphere model of Usenko et al. Supports fields of view beyond 180 degrees.
+ * The model looks along +Z. A negative fx mirrors z so that the camera looks along -Z like the pinhole.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct DoubleSphere<F: Float> {
+    fx: F,
+    fy: F,
+    cx: F,
+    cy: F,
+    xi: F,
+    alpha: F
+}
+
+impl<F: Float> DoubleSphere<F> {
+    pub fn new(fx: F, fy: F, cx: F, cy: F, xi: F, alpha: F) -> DoubleSphere<F> {
+        assert!(alpha >= F::zero() && alpha <= F::one(), "alpha has to be in [0,1]");
+        DoubleSphere {fx, fy, cx, cy, xi, alpha}
+    }
+
+    pub fn get_xi(&self) -> F {self.xi}
+    pub fn get_alpha(&self) -> F {self.alpha}
+
+    fn forward_sign(&self) -> F {
+        self.fx.signum()
+    }
+
+    fn is_in_projection_domain(&self, p: &Vector3<F>) -> bool {
+        let w1 = match self.alpha <= float::from_f64(0.5) {
+            true => self.alpha/(F::one()-self.alpha),
+            false => (F::one()-self.alpha)/self.alpha
+        };
+        let w2 = (w1 + self.xi)/(float::from_f64::<F>(2.0)*w1*self.xi + self.xi*self.xi + F::one()).sqrt();
+        p.z > -w2*p.norm()
+    }
+
+    fn is_in_unprojection_domain(&self, r2: F) -> bool {
+        self.alpha <= float::from_f64(0.5) || r2 <= F::one()/(float::from_f64::<F>(2.0)*self.alpha - F::one())
+    }
+}
+
+impl<F: Float> CameraModel<F> for DoubleSphere<F> {
+    fn name(&self) -> &'static str {NAME}
+
+    fn project(&self, p_cam: &Vector3<F>) -> Option<Vector2<F>> {
+        let sign = self.forward_sign();
+        let p = mirror_z(p_cam, sign);
+        if !self.is_in_projection_domain(&p) {
+            return None;
+        }
+        let d1 = p.norm();
+        let zeta = self.xi*d1 + p.z;
+        let d2 = (p.x*p.x + p.y*p.y + zeta*zeta).sqrt();
+        let denom = self.alpha*d2 + (F::one()-self.alpha)*zeta;
+        match denom > F::zero() {
+            true => Some(Vector2::new(self.fx*sign*p.x/denom + self.cx, self.fy*sign*p.y/denom + self.cy)),
+            false => None
+        }
+    }
+
+    fn unproject(&self, p_image: &Vector2<F>) -> Option<Vector3<F>> {
+        let sign = self.forward_sign();
+        let mx = (p_image.x - self.cx)/(self.fx*sign);
+        let my = (p_image.y - self.cy)/(self.fy*sign);
+        let r2 = mx*mx + my*my;
+        if !self.is_in_unprojection_domain(r2) {
+            return None;
+        }
+        let alpha = self.alpha;
+        let mz = (F::one() - alpha*alpha*r2)/(alpha*(F::one() - (float::from_f64::<F>(2.0)*alpha - F::one())*r2).sqrt() + F::one() - alpha);
+        let factor = (mz*self.xi + (mz*mz + (F::one() - self.xi*self.xi)*r2).sqrt())/(mz*mz + r2);
+        let bearing = Vector3::new(factor*mx, factor*my, factor*mz - self.xi).normalize();
+        Some(mirror_z(&bearing, sign))
+    }
+
+    fn jacobian(&self, p_cam: &Vector3<F>) -> Matrix2x3<F> {
+        let sign = self.forward_sign();
+        let p = mirror_z(p_cam, sign);
+        let d1 = p.norm();
+        let zeta = self.xi*d1 + p.z;
+        let d2 = (p.x*p.x + p.y*p.y + zeta*zeta).sqrt();
+        let denom = self.alpha*d2 + (F::one()-self.alpha)*zeta;
+        let d_zeta = p*(self.xi/d1) + Vector3::z();
+        let d_d2 = (Vector3::new(p.x, p.y, F::zero()) + d_zeta*zeta)/d2;
+        let d_denom = d_d2*self.alpha + d_zeta*(F::one()-self.alpha);
+        let d_u = (Vector3::x()/denom - d_denom*(p.x/(denom*denom)))*(self.fx*sign);
+        let d_v = (Vector3::y()/denom - d_denom*(p.y/(denom*denom)))*(self.fy*sign);
+        let mut jacobian = Matrix2x3::from_rows(&[d_u.transpose(), d_v.transpose()]);
+        jacobian.column_mut(2).scale_mut(sign);
+        jacobian
+    }
+
+    fn convention(&self) -> Convention {
+        Convention::from_forward_sign(self.forward_sign())
+    }
+
+    fn flip_convention(&self, image_height: F) -> DoubleSphere<F> {
+        DoubleSphere::new(-self.fx, -self.fy, self.cx, image_height - self.cy, self.xi, self.alpha)
+    }
+
+    /**
+     * [fx, fy, cx, cy, xi, alpha]
+     */
+    fn get_parameters(&self) -> Vec<F> {
+        vec![self.fx,self.fy,self.cx,self.cy,self.xi,self.alpha]
+    }
+
+    fn from_parameters(name: &str, parameters: &[F]) -> Option<DoubleSphere<F>> {
+        match (name, parameters) {
+            (NAME, &[fx,fy,cx,cy,xi,alpha]) if alpha >= F::zero() && alpha <= F::one() => Some(DoubleSphere::new(fx, fy, cx, cy, xi, alpha)),
+            _ => None
+        }
+    }
+}
@@ -0,0 +1,125 @@
+extern crate nalgebra as na;
+
+use na::{Vector2,Vector3,Matrix2x3};
+use crate::float::{self,Float};
+use crate::convention::Convention;
+use super::{CameraModel,mirror_z};
+
+pub const NAME: &str = "equirectangular";
+
+/**
+ * Spherical 360 degree camera. Longitude around the vertical axis and latitude are mapped linearly to pixels:
+ * u = fx*longitude + cx, v = fy*latitude + cy, with longitude 0 on the optical axis.
+ * The model looks along +Z. A negative fx mirrors z so that the camera looks along -Z like the pinhole.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Equirectangular<F: Float> {
+    fx: F,
+    fy: F,
+    cx: F,
+    cy: F
+}
+
+impl<F: Float> Equirectangular<F> {
+    pub fn new(fx: F, fy: F, cx: F, cy: F) -> Equirectangular<F> {
+        Equirectangular {fx, fy, cx, cy}
+    }
+
+    /**
+     * Covers the full sphere with an image of the given size, looking along +Z. Negate the focal lengths to look along -Z
+     */
+    pub fn full_sphere(width: usize, height: usize) -> Equirectangular<F> {
+        let two = float::from_f64::<F>(2.0);
+        Equirectangular::new(
+            float::from_usize::<F>(width)/(two*F::pi()),
+            float::from_usize::<F>(height)/F::pi(),
+            float::from_usize::<F>(width)/two,
+            float::from_usize::<F>(height)/two)
+    }
+
+    fn forward_sign(&self) -> F {
+        self.fx.signum()
+    }
+}
+
+impl<F: Float> CameraModel<F> for Equirectangular<F> {
+    fn name(&self) -> &'static str {NAME}
+
+    fn project(&self, p_cam: &Vector3<F>) -> Option<Vector2<F>> {
+        let sign = self.forward_sign();
+        let p = mirror_z(p_cam, sign);
+        let rho = (p.x*p.x + p.z*p.z).sqrt();
+        if rho == F::zero() && p.y == F::zero() {
+            return None;
+        }
+        let longitude = p.x.atan2(p.z);
+        let latitude = p.y.atan2(rho);
+        Some(Vector2::new(self.fx*sign*longitude + self.cx, self.fy*sign*latitude + self.cy))
+    }
+
+    fn unproject(&self, p_image: &Vector2<F>) -> Option<Vector3<F>> {
+        let sign = self.forward_sign();
+        let longitude = (p_image.x - self.cx)/(self.fx*sign);
+        let latitude = (p_image.y - self.cy)/(self.fy*sign);
+        if longitude.abs() > F::pi() || latitude.abs() > F::frac_pi_2() {
+            return None;
+        }
+        let bearing = Vector3::new(latitude.cos()*longitude.sin(), latitude.sin(), latitude.cos()*longitude.cos());
+        Some(mirror_z(&bearing, sign))
+    }
+
+    fn jacobian(&self, p_cam: &Vector3<F>) -> Matrix2x3<F> {
+        let sign = self.forward_sign();
+        let p = mirror_z(p_cam, sign);
+        let rho2 = p.x*p.x + p.z*p.z;
+        let rho = rho2.sqrt();
+        let d2 = rho2 + p.y*p.y;
+        let d_longitude = Vector3::new(p.z/rho2, F::zero(), -p.x/rho2);
+        let d_latitude = Vector3::new(-p.y*p.x/(rho*d2), rho/d2, -p.y*p.z/(rho*d2));
+        let mut jacobian = Matrix2x3::from_rows(&[(d_longitude*(self.fx*sign)).transpose(), (d_latitude*(self.fy*sign)).transpose()]);
+        jacobian.column_mut(2).scale_mut(sign);
+        jacobian
+    }
+
+    /**
+     * The image wraps around at longitude +-PI
+     */
+    fn horizontal_period(&self) -> Option<F> {
+        Some(self.fx.abs()*float::from_f64::<F>(2.0)*F::pi())
+    }
+
+    fn convention(&self) -> Convention {
+        Convention::from_forward_sign(self.forward_sign())
+    }
+
+    fn flip_convention(&self, image_height: F) -> Equirectangular<F> {
+        Equirectangular::new(-self.fx, -self.fy, self.cx, image_height - self.cy)
+    }
+
+    /**
+     * [fx, fy, cx, cy]
+     */
+    fn get_parameters(&self) -> Vec<F> {
+        vec![self.fx,self.fy,self.cx,self.cy]
+    }
+
+    fn from_parameters(name: &str, parameters: &[F]) -> Option<Equirectangular<F>> {
+        match (name, parameters) {
+            (NAME, &[fx,fy,cx,cy]) => Some(Equirectangular::new(fx, fy, cx, cy)),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn seam_spans_one_period() {
+        let model = Equirectangular::<f64>::full_sphere(1024, 512);
+        let left = model.project(&Vector3::new(-1e-9, 0.0, -1.0)).unwrap();
+        let right = model.project(&Vector3::new(1e-9, 0.0, -1.0)).unwrap();
+        assert!((right.x - left.x - model.horizontal_period().unwrap()).abs() < 1e-6);
+    }
+}
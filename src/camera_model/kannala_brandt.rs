@@ -0,0 +1,165 @@
+extern crate nalgebra as na;
+
+use na::{Vector2,Vector3,Matrix2x3};
+use crate::float::{self,Float};
+use crate::convention::Convention;
+use super::{CameraModel,mirror_z};
+
+pub const NAME: &str = "kannala_brandt";
+
+const MAX_UNPROJECTION_ITERATIONS: usize = 20;
+// Number of samples used to search the fold of the distortion polynomial on [0,PI]
+const FOLD_SEARCH_SAMPLES: usize = 1000;
+
+/**
+ * Kannala-Brandt equidistant fisheye model as used by OpenCV's fisheye module.
+ * The distorted angle theta_d = theta*(1 + k1*theta^2 + k2*theta^4 + k3*theta^6 + k4*theta^8) is mapped linearly to the image,
+ * so angles beyond 90 degrees from the optical axis can be imaged.
+ * The model looks along +Z. A negative fx mirrors z so that the camera looks along -Z like the pinhole.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct KannalaBrandt<F: Float> {
+    fx: F,
+    fy: F,
+    cx: F,
+    cy: F,
+    k1: F,
+    k2: F,
+    k3: F,
+    k4: F,
+    // Largest angle to the optical axis for which theta_d is increasing
+    max_theta: F
+}
+
+impl<F: Float> KannalaBrandt<F> {
+    pub fn new(fx: F, fy: F, cx: F, cy: F, distortion_coefficients: [F;4]) -> KannalaBrandt<F> {
+        let [k1,k2,k3,k4] = distortion_coefficients;
+        let max_theta = Self::find_max_theta(k1, k2, k3, k4);
+        KannalaBrandt {fx, fy, cx, cy, k1, k2, k3, k4, max_theta}
+    }
+
+    pub fn get_distortion_coefficients(&self) -> [F;4] {[self.k1,self.k2,self.k3,self.k4]}
+
+    /**
+     * Maximum angle to the optical axis in radians that can be imaged
+     */
+    pub fn get_max_theta(&self) -> F {self.max_theta}
+
+    fn forward_sign(&self) -> F {
+        self.fx.signum()
+    }
+
+    fn distort_theta(&self, theta: F) -> F {
+        let theta2 = theta*theta;
+        theta*(F::one() + theta2*(self.k1 + theta2*(self.k2 + theta2*(self.k3 + theta2*self.k4))))
+    }
+
+    fn d_distort_theta(k1: F, k2: F, k3: F, k4: F, theta: F) -> F {
+        let theta2 = theta*theta;
+        F::one() + theta2*(float::from_f64::<F>(3.0)*k1 + theta2*(float::from_f64::<F>(5.0)*k2 + theta2*(float::from_f64::<F>(7.0)*k3 + theta2*float::from_f64::<F>(9.0)*k4)))
+    }
+
+    /**
+     * theta_d has to increase with theta, otherwise different rays are imaged at the same location
+     */
+    fn find_max_theta(k1: F, k2: F, k3: F, k4: F) -> F {
+        let step = F::pi()/float::from_usize::<F>(FOLD_SEARCH_SAMPLES);
+        (1..=FOLD_SEARCH_SAMPLES)
+            .map(|i| float::from_usize::<F>(i)*step)
+            .find(|&theta| Self::d_distort_theta(k1, k2, k3, k4, theta) <= F::zero())
+            .map_or(F::pi(), |theta| theta - step)
+    }
+}
+
+impl<F: Float> CameraModel<F> for KannalaBrandt<F> {
+    fn name(&self) -> &'static str {NAME}
+
+    fn project(&self, p_cam: &Vector3<F>) -> Option<Vector2<F>> {
+        let sign = self.forward_sign();
+        let p = mirror_z(p_cam, sign);
+        let r = (p.x*p.x + p.y*p.y).sqrt();
+        let theta = r.atan2(p.z);
+        if theta >= self.max_theta || (r == F::zero() && p.z <= F::zero()) {
+            return None;
+        }
+        // Near the optical axis theta_d/r converges to 1/z
+        let scale = match r > F::default_epsilon() {
+            true => self.distort_theta(theta)/r,
+            false => F::one()/p.z
+        };
+        Some(Vector2::new(self.fx*sign*p.x*scale + self.cx, self.fy*sign*p.y*scale + self.cy))
+    }
+
+    fn unproject(&self, p_image: &Vector2<F>) -> Option<Vector3<F>> {
+        let sign = self.forward_sign();
+        let mx = (p_image.x - self.cx)/(self.fx*sign);
+        let my = (p_image.y - self.cy)/(self.fy*sign);
+        let theta_d = (mx*mx + my*my).sqrt();
+        if theta_d <= F::default_epsilon() {
+            return Some(mirror_z(&Vector3::z(), sign));
+        }
+        let mut theta = theta_d.min(self.max_theta);
+        for _ in 0..MAX_UNPROJECTION_ITERATIONS {
+            let delta = (self.distort_theta(theta) - theta_d)/Self::d_distort_theta(self.k1, self.k2, self.k3, self.k4, theta);
+            theta -= delta;
+            if delta.abs() <= F::default_epsilon()*(F::one() + theta) {
+                break;
+            }
+        }
+        let converged = (self.distort_theta(theta) - theta_d).abs() <= F::default_epsilon().sqrt();
+        if !converged || theta < F::zero() || theta >= self.max_theta {
+            return None;
+        }
+        let bearing = Vector3::new(theta.sin()*mx/theta_d, theta.sin()*my/theta_d, theta.cos());
+        Some(mirror_z(&bearing, sign))
+    }
+
+    fn jacobian(&self, p_cam: &Vector3<F>) -> Matrix2x3<F> {
+        let sign = self.forward_sign();
+        let p = mirror_z(p_cam, sign);
+        let r2 = p.x*p.x + p.y*p.y;
+        let r = r2.sqrt();
+        let mut jacobian = match r > F::default_epsilon() {
+            true => {
+                let d2 = r2 + p.z*p.z;
+                let theta = r.atan2(p.z);
+                let theta_d = self.distort_theta(theta);
+                let d_theta = Vector3::new(p.z*p.x/(r*d2), p.z*p.y/(r*d2), -r/d2);
+                let d_r = Vector3::new(p.x/r, p.y/r, F::zero());
+                // Gradient of theta_d/r
+                let d_scale = d_theta*(Self::d_distort_theta(self.k1, self.k2, self.k3, self.k4, theta)/r) - d_r*(theta_d/r2);
+                let scale = theta_d/r;
+                let d_u = (Vector3::x()*scale + d_scale*p.x)*(self.fx*sign);
+                let d_v = (Vector3::y()*scale + d_scale*p.y)*(self.fy*sign);
+                Matrix2x3::from_rows(&[d_u.transpose(), d_v.transpose()])
+            },
+            false => Matrix2x3::new(
+                self.fx*sign/p.z, F::zero(), -self.fx*sign*p.x/(p.z*p.z),
+                F::zero(), self.fy*sign/p.z, -self.fy*sign*p.y/(p.z*p.z))
+        };
+        jacobian.column_mut(2).scale_mut(sign);
+        jacobian
+    }
+
+    fn convention(&self) -> Convention {
+        Convention::from_forward_sign(self.forward_sign())
+    }
+
+    fn flip_convention(&self, image_height: F) -> KannalaBrandt<F> {
+        KannalaBrandt::new(-self.fx, -self.fy, self.cx, image_height - self.cy, self.get_distortion_coefficients())
+    }
+
+    /**
+     * [fx, fy, cx, cy, k1, k2, k3, k4]
+     */
+    fn get_parameters(&self) -> Vec<F> {
+        vec![self.fx,self.fy,self.cx,self.cy,self.k1,self.k2,self.k3,self.k4]
+    }
+
+    fn from_parameters(name: &str, parameters: &[F]) -> Option<KannalaBrandt<F>> {
+        match (name, parameters) {
+            (NAME, &[fx,fy,cx,cy,k1,k2,k3,k4]) => Some(KannalaBrandt::new(fx, fy, cx, cy, [k1,k2,k3,k4])),
+            _ => None
+        }
+    }
+}
@@ -0,0 +1,370 @@
+extern crate nalgebra as na;
+
+pub mod affine;
+pub mod double_sphere;
+pub mod equirectangular;
+pub mod kannala_brandt;
+pub mod orthographic;
+pub mod pinhole;
+pub mod radial_tangential;
+pub mod unified;
+pub mod weak_perspective;
+
+use na::{Vector2,Vector3,Matrix2x3};
+use crate::float::Float;
+use crate::convention::Convention;
+
+pub use affine::Affine;
+pub use double_sphere::DoubleSphere;
+pub use equirectangular::Equirectangular;
+pub use kannala_brandt::KannalaBrandt;
+pub use orthographic::Orthographic;
+pub use pinhole::Pinhole;
+pub use radial_tangential::RadialTangential;
+pub use unified::Unified;
+pub use weak_perspective::WeakPerspective;
+
+/**
+ * Maps points in camera space to the image and back.
+ * Points are in the camera frame produced by the view matrix.
+ */
+pub trait CameraModel<F: Float> {
+    /**
+     * Name under which the model is serialized
+     */
+    fn name(&self) -> &'static str;
+
+    /**
+     * Returns None if the point lies outside the valid domain of the model, e.g. behind a perspective camera
+     */
+    fn project(&self, p_cam: &Vector3<F>) -> Option<Vector2<F>>;
+
+    /**
+     * Returns the unit bearing vector of an image point or None if the point has no valid preimage
+     */
+    fn unproject(&self, p_image: &Vector2<F>) -> Option<Vector3<F>>;
+
+    /**
+     * Jacobian of project with respect to the camera space point
+     */
+    fn jacobian(&self, p_cam: &Vector3<F>) -> Matrix2x3<F>;
+
+    /**
+     * Distance used to resolve occlusions. Smaller values are closer to the camera.
+     * For points along the same image ray this has to be monotonic in the distance to the camera.
+     */
+    fn depth(&self, p_cam: &Vector3<F>) -> F {
+        p_cam.norm()
+    }
+
+    /**
+     * How depth varies across a projected triangle, used by the rasterizer
+     */
+    fn depth_interpolation(&self) -> DepthInterpolation {
+        DepthInterpolation::Perspective
+    }
+
+    /**
+     * Width in pixels after which the image repeats horizontally, e.g. at the seam of a 360 degree image.
+     * Triangles crossing the seam are rasterized on both sides.
+     */
+    fn horizontal_period(&self) -> Option<F> {
+        None
+    }
+
+    /**
+     * Convention implied by the viewing direction of the model
+     */
+    fn convention(&self) -> Convention;
+
+    /**
+     * The same camera in the other convention, see Convention. Image rows are flipped within the image height.
+     */
+    fn flip_convention(&self, image_height: F) -> Self where Self: Sized;
+
+    fn get_parameters(&self) -> Vec<F>;
+
+    /**
+     * Positions of (fx, fy) and (cx, cy) in get_parameters, None if the model has no focal length and principal point
+     */
+    fn intrinsic_indices(&self) -> Option<([usize;2],[usize;2])> {
+        Some(([0,1],[2,3]))
+    }
+
+    /**
+     * Inverse of get_parameters. Returns None if the name does not belong to the model or the parameters are malformed
+     */
+    fn from_parameters(name: &str, parameters: &[F]) -> Option<Self> where Self: Sized;
+}
+
+/**
+ * Interpolation of depth between the projected vertices of a triangle
+ */
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum DepthInterpolation {
+    /// Inverse depth is linear in the image. Exact for perspective cameras, an approximation for other central models
+    Perspective,
+    /// Depth is linear in the image. Exact for parallel projections, where depth may also be negative
+    Affine
+}
+
+/**
+ * Models defined along +Z are mirrored to look along -Z when the forward sign is negative
+ */
+pub(crate) fn mirror_z<F: Float>(v: &Vector3<F>, forward_sign: F) -> Vector3<F> {
+    Vector3::new(v.x, v.y, v.z*forward_sign)
+}
+
+/**
+ * Any of the camera models of this crate. Used to load cameras whose model is only known at runtime.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum AnyCameraModel<F: Float> {
+    Pinhole(Pinhole<F>),
+    RadialTangential(RadialTangential<F>),
+    KannalaBrandt(KannalaBrandt<F>),
+    DoubleSphere(DoubleSphere<F>),
+    Unified(Unified<F>),
+    Equirectangular(Equirectangular<F>),
+    Orthographic(Orthographic<F>),
+    WeakPerspective(WeakPerspective<F>),
+    Affine(Affine<F>)
+}
+
+// Forwards a call to the model held by the variant
+macro_rules! dispatch {
+    ($self:ident, $model:ident => $call:expr) => {
+        match $self {
+            AnyCameraModel::Pinhole($model) => $call,
+            AnyCameraModel::RadialTangential($model) => $call,
+            AnyCameraModel::KannalaBrandt($model) => $call,
+            AnyCameraModel::DoubleSphere($model) => $call,
+            AnyCameraModel::Unified($model) => $call,
+            AnyCameraModel::Equirectangular($model) => $call,
+            AnyCameraModel::Orthographic($model) => $call,
+            AnyCameraModel::WeakPerspective($model) => $call,
+            AnyCameraModel::Affine($model) => $call
+        }
+    };
+}
+
+impl<F: Float> CameraModel<F> for AnyCameraModel<F> {
+    fn name(&self) -> &'static str {
+        dispatch!(self, model => model.name())
+    }
+
+    fn project(&self, p_cam: &Vector3<F>) -> Option<Vector2<F>> {
+        dispatch!(self, model => model.project(p_cam))
+    }
+
+    fn unproject(&self, p_image: &Vector2<F>) -> Option<Vector3<F>> {
+        dispatch!(self, model => model.unproject(p_image))
+    }
+
+    fn jacobian(&self, p_cam: &Vector3<F>) -> Matrix2x3<F> {
+        dispatch!(self, model => model.jacobian(p_cam))
+    }
+
+    fn depth(&self, p_cam: &Vector3<F>) -> F {
+        dispatch!(self, model => model.depth(p_cam))
+    }
+
+    fn depth_interpolation(&self) -> DepthInterpolation {
+        dispatch!(self, model => model.depth_interpolation())
+    }
+
+    fn horizontal_period(&self) -> Option<F> {
+        dispatch!(self, model => model.horizontal_period())
+    }
+
+    fn convention(&self) -> Convention {
+        dispatch!(self, model => model.convention())
+    }
+
+    fn flip_convention(&self, image_height: F) -> AnyCameraModel<F> {
+        dispatch!(self, model => model.flip_convention(image_height).into())
+    }
+
+    fn get_parameters(&self) -> Vec<F> {
+        dispatch!(self, model => model.get_parameters())
+    }
+
+    fn intrinsic_indices(&self) -> Option<([usize;2],[usize;2])> {
+        dispatch!(self, model => model.intrinsic_indices())
+    }
+
+    fn from_parameters(name: &str, parameters: &[F]) -> Option<AnyCameraModel<F>> {
+        match name {
+            pinhole::NAME => Pinhole::from_parameters(name, parameters).map(AnyCameraModel::Pinhole),
+            radial_tangential::NAME => RadialTangential::from_parameters(name, parameters).map(AnyCameraModel::RadialTangential),
+            kannala_brandt::NAME => KannalaBrandt::from_parameters(name, parameters).map(AnyCameraModel::KannalaBrandt),
+            double_sphere::NAME => DoubleSphere::from_parameters(name, parameters).map(AnyCameraModel::DoubleSphere),
+            unified::NAME => Unified::from_parameters(name, parameters).map(AnyCameraModel::Unified),
+            equirectangular::NAME => Equirectangular::from_parameters(name, parameters).map(AnyCameraModel::Equirectangular),
+            orthographic::NAME => Orthographic::from_parameters(name, parameters).map(AnyCameraModel::Orthographic),
+            weak_perspective::NAME => WeakPerspective::from_parameters(name, parameters).map(AnyCameraModel::WeakPerspective),
+            affine::NAME => Affine::from_parameters(name, parameters).map(AnyCameraModel::Affine),
+            _ => None
+        }
+    }
+}
+
+// Allows passing any concrete model where an AnyCameraModel is expected
+macro_rules! impl_from_model {
+    ($($variant:ident),*) => {
+        $(impl<F: Float> From<$variant<F>> for AnyCameraModel<F> {
+            fn from(model: $variant<F>) -> AnyCameraModel<F> {
+                AnyCameraModel::$variant(model)
+            }
+        })*
+    };
+}
+
+impl_from_model!(Pinhole, RadialTangential, KannalaBrandt, DoubleSphere, Unified, Equirectangular, Orthographic, WeakPerspective, Affine);
+
+#[cfg(test)]
+mod test_util {
+    use std::collections::HashMap;
+    use na::{Vector2,Vector3,Matrix2x3,Matrix2x4,Matrix3x4};
+    use crate::camera::Resolution;
+    use crate::camera_features::CameraFeatures;
+    use crate::feature::Feature;
+    use super::*;
+
+    const IMAGE_HEIGHT: f64 = 480.0;
+
+    /**
+     * Every model in the OpenGL convention with the largest angle off the optical axis it is tested at
+     */
+    fn models() -> Vec<(AnyCameraModel<f64>, f64)> {
+        let pinhole = Pinhole::from_focal_and_principal_point(-500.0, -480.0, 320.0, 240.0, 2.0);
+        vec![
+            (AnyCameraModel::Pinhole(pinhole), 0.6),
+            (AnyCameraModel::RadialTangential(RadialTangential::new(pinhole, [-0.28, 0.07, 0.0002, 0.00002, 0.01])), 0.6),
+            (AnyCameraModel::KannalaBrandt(KannalaBrandt::new(-380.0, -380.0, 320.0, 240.0, [0.01, -0.005, 0.001, -0.0002])), 1.4),
+            (AnyCameraModel::DoubleSphere(DoubleSphere::new(-310.0, -310.0, 320.0, 240.0, -0.2, 0.6)), 1.4),
+            (AnyCameraModel::Unified(Unified::new(-400.0, -400.0, 320.0, 240.0, 0.6)), 1.4),
+            (AnyCameraModel::Equirectangular(Equirectangular::full_sphere(1024, 512).flip_convention(512.0)), 2.5),
+            (AnyCameraModel::Orthographic(Orthographic::new(-100.0, -120.0, 320.0, 240.0)), 0.6),
+            (AnyCameraModel::WeakPerspective(WeakPerspective::new(-500.0, -500.0, 320.0, 240.0, 4.0)), 0.6),
+            (AnyCameraModel::Affine(Affine::new(Matrix2x4::new(
+                -90.0, 10.0, 5.0, 320.0,
+                3.0, -110.0, -8.0, 240.0), 1.0)), 0.6)
+        ]
+    }
+
+    /**
+     * Both conventions of every model
+     */
+    fn models_in_both_conventions() -> Vec<(AnyCameraModel<f64>, f64)> {
+        models().into_iter().flat_map(|(model, max_angle)| [(model, max_angle), (model.flip_convention(IMAGE_HEIGHT), max_angle)]).collect()
+    }
+
+    /**
+     * Points in front of the camera up to max_angle radians off the optical axis, at several depths
+     */
+    fn front_points<C: CameraModel<f64>>(model: &C, max_angle: f64) -> Vec<Vector3<f64>> {
+        let forward = model.convention().forward_sign::<f64>();
+        let mut points = vec![];
+        for angle in [0.0, 0.3, 0.6, 0.9].map(|s| s*max_angle) {
+            for azimuth in (0..6).map(|i| 1.0 + i as f64) {
+                for depth in [0.5, 3.0] {
+                    points.push(Vector3::new(angle.sin()*azimuth.cos(), angle.sin()*azimuth.sin(), angle.cos()*forward)*depth);
+                }
+            }
+        }
+        points
+    }
+
+    #[test]
+    fn models_cover_every_variant() {
+        let mut names = models().iter().map(|(model,_)| model.name()).collect::<Vec<_>>();
+        names.sort();
+        names.dedup();
+        assert_eq!(names.len(), 9);
+    }
+
+    /**
+     * Central models unproject a projected point to its bearing. Parallel models keep the projection of points moved along
+     * the unprojected ray, whose depth increases by the distance moved.
+     */
+    #[test]
+    fn round_trip() {
+        for (model, max_angle) in models_in_both_conventions() {
+            for p in front_points(&model, max_angle) {
+                let p_image = model.project(&p).unwrap_or_else(|| panic!("{} can not project {:?}", model.name(), p));
+                let direction = model.unproject(&p_image).unwrap_or_else(|| panic!("{} can not unproject {:?}", model.name(), p_image));
+                match model.depth_interpolation() {
+                    DepthInterpolation::Perspective => assert!((direction - p.normalize()).norm() < 1e-8, "{}: bearing {:?} of {:?}", model.name(), direction, p),
+                    DepthInterpolation::Affine => for t in [-2.0, 0.5, 4.0] {
+                        let moved = p + direction*t;
+                        assert!((model.project(&moved).unwrap() - p_image).norm() < 1e-9, "{}: {:?} moves in the image", model.name(), moved);
+                        assert!((model.depth(&moved) - model.depth(&p) - t).abs() < 1e-9, "{}: depth of {:?}", model.name(), moved);
+                    }
+                }
+            }
+        }
+    }
+
+    /**
+     * The analytic Jacobian matches central differences
+     */
+    #[test]
+    fn jacobian() {
+        let h = 1e-6;
+        for (model, max_angle) in models_in_both_conventions() {
+            for p in front_points(&model, max_angle) {
+                let jacobian = model.jacobian(&p);
+                let numeric = Matrix2x3::from_fn(|r,c| {
+                    let offset = Vector3::ith(c, h);
+                    (model.project(&(p + offset)).unwrap()[r] - model.project(&(p - offset)).unwrap()[r])/(2.0*h)
+                });
+                assert!((jacobian - numeric).norm() < 1e-5*jacobian.norm().max(1.0), "{}: jacobian {} differs from {} at {:?}", model.name(), jacobian, numeric, p);
+            }
+        }
+    }
+
+    #[test]
+    fn parameters_round_trip() {
+        for (model, _) in models_in_both_conventions() {
+            assert_eq!(AnyCameraModel::from_parameters(model.name(), &model.get_parameters()), Some(model));
+        }
+    }
+
+    /**
+     * CameraFeatures::to_convention flips the model with flip_convention. The converted features have to be the projections
+     * of the flipped model seen through the converted view.
+     */
+    #[test]
+    fn flip_convention_matches_to_convention() {
+        for (model, max_angle) in models_in_both_conventions() {
+            let points = front_points(&model, max_angle);
+            let feature_map = points.iter().enumerate()
+                .map(|(id,p)| {
+                    let p_image = model.project(p).unwrap();
+                    (id, Feature::from_parts(&p_image, &Vector2::new(0, 0)).with_bearing(model.unproject(&p_image)))
+                })
+                .collect::<HashMap<_,_>>();
+            let camera_features = CameraFeatures::new(feature_map, 0, Matrix3x4::identity(), &model, Resolution::new(640, IMAGE_HEIGHT as usize));
+            let target = match model.convention() {
+                Convention::OpenGl => Convention::OpenCv,
+                Convention::OpenCv => Convention::OpenGl
+            };
+
+            let converted = camera_features.to_convention(target).unwrap();
+            let flipped = model.flip_convention(IMAGE_HEIGHT);
+            assert_eq!(converted.get_convention(), target);
+            assert_eq!(flipped.convention(), target);
+            assert_eq!(converted.get_camera_model::<AnyCameraModel<f64>>(), Some(flipped));
+            for (id,p) in points.iter().enumerate() {
+                let p_target = converted.get_view_matrix()*p.push(1.0);
+                let expected = flipped.project(&p_target).unwrap();
+                let feature = converted.get_feature_map()[&id];
+                assert!((feature.get_position() - expected).norm() < 1e-9, "{}: {:?} differs from {:?}", model.name(), feature.get_position(), expected);
+                if let (Some(bearing), Some(expected_bearing)) = (feature.get_bearing(), flipped.unproject(&expected)) {
+                    assert!((bearing - expected_bearing).norm() < 1e-9, "{}: bearing {:?} differs from {:?}", model.name(), bearing, expected_bearing);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,87 @@
+extern crate nalgebra as na;
+
+use na::{Vector2,Vector3,Matrix2x3};
+use crate::float::Float;
+use crate::convention::Convention;
+use super::{CameraModel,DepthInterpolation};
+
+pub const NAME: &str = "orthographic";
+
+/**
+ * Parallel projection along the optical axis: u = sx*x + cx, v = sy*y + cy with scales in pixels per unit.
+ * The model looks along +Z. A negative sx makes the camera look along -Z like the pinhole.
+ * Points behind the camera are projected as well.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Orthographic<F: Float> {
+    sx: F,
+    sy: F,
+    cx: F,
+    cy: F
+}
+
+impl<F: Float> Orthographic<F> {
+    pub fn new(sx: F, sy: F, cx: F, cy: F) -> Orthographic<F> {
+        Orthographic {sx, sy, cx, cy}
+    }
+
+    fn forward_sign(&self) -> F {
+        self.sx.signum()
+    }
+}
+
+impl<F: Float> CameraModel<F> for Orthographic<F> {
+    fn name(&self) -> &'static str {NAME}
+
+    fn project(&self, p_cam: &Vector3<F>) -> Option<Vector2<F>> {
+        let sign = self.forward_sign();
+        Some(Vector2::new(self.sx*sign*p_cam.x + self.cx, self.sy*sign*p_cam.y + self.cy))
+    }
+
+    /**
+     * All pixels share the viewing direction
+     */
+    fn unproject(&self, _p_image: &Vector2<F>) -> Option<Vector3<F>> {
+        Some(Vector3::z()*self.forward_sign())
+    }
+
+    fn jacobian(&self, _p_cam: &Vector3<F>) -> Matrix2x3<F> {
+        let sign = self.forward_sign();
+        Matrix2x3::new(
+            self.sx*sign, F::zero(), F::zero(),
+            F::zero(), self.sy*sign, F::zero())
+    }
+
+    /**
+     * Signed distance along the viewing direction
+     */
+    fn depth(&self, p_cam: &Vector3<F>) -> F {
+        p_cam.z*self.forward_sign()
+    }
+
+    fn depth_interpolation(&self) -> DepthInterpolation {
+        DepthInterpolation::Affine
+    }
+
+    fn convention(&self) -> Convention {
+        Convention::from_forward_sign(self.forward_sign())
+    }
+
+    fn flip_convention(&self, image_height: F) -> Orthographic<F> {
+        Orthographic::new(-self.sx, -self.sy, self.cx, image_height - self.cy)
+    }
+
+    /**
+     * [sx, sy, cx, cy]
+     */
+    fn get_parameters(&self) -> Vec<F> {
+        vec![self.sx,self.sy,self.cx,self.cy]
+    }
+
+    fn from_parameters(name: &str, parameters: &[F]) -> Option<Orthographic<F>> {
+        match (name, parameters) {
+            (NAME, &[sx,sy,cx,cy]) => Some(Orthographic::new(sx, sy, cx, cy)),
+            _ => None
+        }
+    }
+}
@@ -0,0 +1,112 @@
+extern crate nalgebra as na;
+
+use na::{Vector2,Vector3,Matrix3,Matrix2x3};
+use crate::float::Float;
+use crate::convention::Convention;
+use super::CameraModel;
+
+pub const NAME: &str = "pinhole";
+
+/**
+ * Linear perspective camera defined by an upper triangular intrinsic matrix.
+ * The sign of the focal length selects the viewing direction: a negative focal length looks along -Z (Computer Graphics),
+ * a positive focal length along +Z (Computer Vision).
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Pinhole<F: Float> {
+    intrinsic_matrix: Matrix3<F>
+}
+
+impl<F: Float> Pinhole<F> {
+    pub fn new(intrinsic_matrix: Matrix3<F>) -> Pinhole<F> {
+        Pinhole {intrinsic_matrix}
+    }
+
+    pub fn from_focal_and_principal_point(fx: F, fy: F, cx: F, cy: F, skew: F) -> Pinhole<F> {
+        Pinhole::new(Matrix3::<F>::new(
+            fx, skew, cx,
+            F::zero(), fy, cy,
+            F::zero(), F::zero(), F::one()))
+    }
+
+    pub fn get_intrinsic_matrix(&self) -> &Matrix3<F> {&self.intrinsic_matrix}
+
+    /**
+     * 1 if the camera looks along +Z, -1 if it looks along -Z
+     */
+    pub(crate) fn forward_sign(&self) -> F {
+        self.intrinsic_matrix[(0,0)].signum()
+    }
+}
+
+impl<F: Float> CameraModel<F> for Pinhole<F> {
+    fn name(&self) -> &'static str {NAME}
+
+    fn project(&self, p_cam: &Vector3<F>) -> Option<Vector2<F>> {
+        let p = self.intrinsic_matrix*p_cam;
+        match p.z*self.forward_sign() > F::zero() {
+            true => Some(Vector2::new(p.x/p.z,p.y/p.z)),
+            false => None
+        }
+    }
+
+    fn unproject(&self, p_image: &Vector2<F>) -> Option<Vector3<F>> {
+        let inverse = self.intrinsic_matrix.try_inverse()?;
+        let ray = inverse*Vector3::new(p_image.x,p_image.y,F::one())*self.forward_sign();
+        Some(ray.normalize())
+    }
+
+    fn jacobian(&self, p_cam: &Vector3<F>) -> Matrix2x3<F> {
+        let p = self.intrinsic_matrix*p_cam;
+        let u = p.x/p.z;
+        let v = p.y/p.z;
+        let k = &self.intrinsic_matrix;
+        Matrix2x3::<F>::from_rows(&[
+            (k.row(0) - k.row(2)*u)/p.z,
+            (k.row(1) - k.row(2)*v)/p.z
+        ])
+    }
+
+    /**
+     * Distance along the optical axis. Interpolates exactly in screen space.
+     */
+    fn depth(&self, p_cam: &Vector3<F>) -> F {
+        (self.intrinsic_matrix*p_cam).z*self.forward_sign()
+    }
+
+    fn convention(&self) -> Convention {
+        Convention::from_forward_sign(self.forward_sign())
+    }
+
+    fn flip_convention(&self, image_height: F) -> Pinhole<F> {
+        let k = &self.intrinsic_matrix;
+        Pinhole::from_focal_and_principal_point(-k[(0,0)], -k[(1,1)], k[(0,2)], image_height - k[(1,2)], k[(0,1)])
+    }
+
+    /**
+     * [fx, fy, cx, cy, skew]
+     */
+    fn get_parameters(&self) -> Vec<F> {
+        let k = &self.intrinsic_matrix;
+        vec![k[(0,0)],k[(1,1)],k[(0,2)],k[(1,2)],k[(0,1)]]
+    }
+
+    fn from_parameters(name: &str, parameters: &[F]) -> Option<Pinhole<F>> {
+        match (name, parameters) {
+            (NAME, &[fx,fy,cx,cy,skew]) => Some(Pinhole::from_focal_and_principal_point(fx, fy, cx, cy, skew)),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_behind_are_not_projected() {
+        let model = Pinhole::from_focal_and_principal_point(-500.0, -480.0, 320.0, 240.0, 2.0);
+        assert_eq!(model.project(&Vector3::new(0.1, 0.2, 1.0)), None);
+        assert_eq!(model.flip_convention(480.0).project(&Vector3::new(0.1, 0.2, -1.0)), None);
+    }
+}
@@ -0,0 +1,209 @@
+extern crate nalgebra as na;
+
+use na::{Vector2,Vector3,Matrix2,Matrix2x3};
+use crate::float::{self,Float};
+use crate::convention::Convention;
+use super::{CameraModel,Pinhole};
+
+pub const NAME: &str = "radial_tangential";
+
+const MAX_UNDISTORTION_ITERATIONS: usize = 20;
+// Squared normalized radii searched for the fold of the distortion polynomial
+const FOLD_SEARCH_START: f64 = 1e-4;
+const FOLD_SEARCH_END: f64 = 1e6;
+const FOLD_SEARCH_STEP: f64 = 1.01;
+
+/**
+ * Pinhole camera with Brown-Conrady lens distortion as used by OpenCV.
+ * Distortion is applied to the normalized image coordinates (x/z, y/z) before the intrinsic matrix.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct RadialTangential<F: Float> {
+    pinhole: Pinhole<F>,
+    k1: F,
+    k2: F,
+    p1: F,
+    p2: F,
+    k3: F,
+    // Squared normalized radius at which the radial distortion folds back, None if it never does
+    max_radius_squared: Option<F>
+}
+
+impl<F: Float> RadialTangential<F> {
+    /**
+     * Coefficients are given in OpenCV order [k1, k2, p1, p2, k3]
+     */
+    pub fn new(pinhole: Pinhole<F>, distortion_coefficients: [F;5]) -> RadialTangential<F> {
+        let [k1,k2,p1,p2,k3] = distortion_coefficients;
+        let max_radius_squared = Self::find_fold(k1, k2, k3);
+        RadialTangential {pinhole, k1, k2, p1, p2, k3, max_radius_squared}
+    }
+
+    pub fn get_pinhole(&self) -> &Pinhole<F> {&self.pinhole}
+    pub fn get_distortion_coefficients(&self) -> [F;5] {[self.k1,self.k2,self.p1,self.p2,self.k3]}
+
+    /**
+     * Maps undistorted normalized coordinates to distorted normalized coordinates
+     */
+    pub fn distort(&self, p_norm: &Vector2<F>) -> Vector2<F> {
+        let two = float::from_f64::<F>(2.0);
+        let (x,y) = (p_norm.x,p_norm.y);
+        let r2 = x*x+y*y;
+        let radial = F::one() + r2*(self.k1 + r2*(self.k2 + r2*self.k3));
+        Vector2::new(
+            x*radial + two*self.p1*x*y + self.p2*(r2 + two*x*x),
+            y*radial + self.p1*(r2 + two*y*y) + two*self.p2*x*y
+        )
+    }
+
+    /**
+     * Inverse of distort using Gauss-Newton. Returns None if the iteration does not converge.
+     */
+    pub fn undistort(&self, p_distorted: &Vector2<F>) -> Option<Vector2<F>> {
+        let mut p_norm = *p_distorted;
+        for _ in 0..MAX_UNDISTORTION_ITERATIONS {
+            let residual = self.distort(&p_norm) - p_distorted;
+            let delta = self.distortion_jacobian(&p_norm).try_inverse()?*residual;
+            p_norm -= delta;
+            if delta.norm() <= F::default_epsilon()*(F::one() + p_norm.norm()) {
+                break;
+            }
+        }
+        let converged = (self.distort(&p_norm) - p_distorted).norm() <= F::default_epsilon().sqrt();
+        match converged && self.is_radially_monotonic(p_norm.norm_squared()) {
+            true => Some(p_norm),
+            false => None
+        }
+    }
+
+    /**
+     * Jacobian of distort with respect to the undistorted normalized coordinates
+     */
+    fn distortion_jacobian(&self, p_norm: &Vector2<F>) -> Matrix2<F> {
+        let two = float::from_f64::<F>(2.0);
+        let six = float::from_f64::<F>(6.0);
+        let (x,y) = (p_norm.x,p_norm.y);
+        let r2 = x*x+y*y;
+        let radial = F::one() + r2*(self.k1 + r2*(self.k2 + r2*self.k3));
+        // d radial / d r2
+        let d_radial = self.k1 + r2*(two*self.k2 + float::from_f64::<F>(3.0)*r2*self.k3);
+        let cross = two*x*y*d_radial + two*self.p1*x + two*self.p2*y;
+        Matrix2::new(
+            radial + two*x*x*d_radial + two*self.p1*y + six*self.p2*x, cross,
+            cross, radial + two*y*y*d_radial + six*self.p1*y + two*self.p2*x
+        )
+    }
+
+    /**
+     * The radial distortion polynomial folds back beyond the radius where r*radial(r) stops increasing.
+     * Points past that radius would be imaged at wrong locations.
+     */
+    fn is_radially_monotonic(&self, r2: F) -> bool {
+        self.max_radius_squared.is_none_or(|max_r2| r2 < max_r2)
+    }
+
+    /**
+     * Smallest squared radius where the derivative of r*radial(r) becomes zero
+     */
+    fn find_fold(k1: F, k2: F, k3: F) -> Option<F> {
+        let d_radius = |r2: F| F::one() + r2*(float::from_f64::<F>(3.0)*k1 + r2*(float::from_f64::<F>(5.0)*k2 + r2*float::from_f64::<F>(7.0)*k3));
+        let mut lower = F::zero();
+        let mut upper = float::from_f64::<F>(FOLD_SEARCH_START);
+        while upper < float::from_f64::<F>(FOLD_SEARCH_END) {
+            if d_radius(upper) <= F::zero() {
+                for _ in 0..64 {
+                    let mid = (lower+upper)/float::from_f64::<F>(2.0);
+                    match d_radius(mid) > F::zero() {
+                        true => lower = mid,
+                        false => upper = mid
+                    }
+                }
+                return Some(lower);
+            }
+            lower = upper;
+            upper *= float::from_f64::<F>(FOLD_SEARCH_STEP);
+        }
+        None
+    }
+}
+
+impl<F: Float> CameraModel<F> for RadialTangential<F> {
+    fn name(&self) -> &'static str {NAME}
+
+    fn project(&self, p_cam: &Vector3<F>) -> Option<Vector2<F>> {
+        if self.pinhole.depth(p_cam) <= F::zero() {
+            return None;
+        }
+        let p_norm = Vector2::new(p_cam.x/p_cam.z,p_cam.y/p_cam.z);
+        if !self.is_radially_monotonic(p_norm.norm_squared()) {
+            return None;
+        }
+        let p_distorted = self.distort(&p_norm);
+        let p = self.pinhole.get_intrinsic_matrix()*Vector3::new(p_distorted.x,p_distorted.y,F::one());
+        Some(Vector2::new(p.x,p.y))
+    }
+
+    fn unproject(&self, p_image: &Vector2<F>) -> Option<Vector3<F>> {
+        let inverse = self.pinhole.get_intrinsic_matrix().try_inverse()?;
+        let p_distorted = inverse*Vector3::new(p_image.x,p_image.y,F::one());
+        let p_norm = self.undistort(&Vector2::new(p_distorted.x,p_distorted.y))?;
+        Some((Vector3::new(p_norm.x,p_norm.y,F::one())*self.pinhole.forward_sign()).normalize())
+    }
+
+    fn jacobian(&self, p_cam: &Vector3<F>) -> Matrix2x3<F> {
+        let z = p_cam.z;
+        let p_norm = Vector2::new(p_cam.x/z,p_cam.y/z);
+        let d_norm = Matrix2x3::new(
+            F::one()/z, F::zero(), -p_norm.x/z,
+            F::zero(), F::one()/z, -p_norm.y/z);
+        let k = self.pinhole.get_intrinsic_matrix().fixed_view::<2,2>(0,0).into_owned();
+        k*self.distortion_jacobian(&p_norm)*d_norm
+    }
+
+    /**
+     * Distance along the optical axis, as for the undistorted pinhole
+     */
+    fn depth(&self, p_cam: &Vector3<F>) -> F {
+        self.pinhole.depth(p_cam)
+    }
+
+    fn convention(&self) -> Convention {
+        self.pinhole.convention()
+    }
+
+    /**
+     * Normalized x changes sign between the conventions, so the tangential coefficient p2 does as well
+     */
+    fn flip_convention(&self, image_height: F) -> RadialTangential<F> {
+        RadialTangential::new(self.pinhole.flip_convention(image_height), [self.k1,self.k2,self.p1,-self.p2,self.k3])
+    }
+
+    /**
+     * [fx, fy, cx, cy, skew, k1, k2, p1, p2, k3]
+     */
+    fn get_parameters(&self) -> Vec<F> {
+        let mut parameters = self.pinhole.get_parameters();
+        parameters.extend_from_slice(&self.get_distortion_coefficients());
+        parameters
+    }
+
+    fn from_parameters(name: &str, parameters: &[F]) -> Option<RadialTangential<F>> {
+        match (name, parameters) {
+            (NAME, &[fx,fy,cx,cy,skew,k1,k2,p1,p2,k3]) => Some(RadialTangential::new(Pinhole::from_focal_and_principal_point(fx, fy, cx, cy, skew), [k1,k2,p1,p2,k3])),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn points_beyond_the_fold_are_not_projected() {
+        // r*(1 - 0.5*r^2) stops increasing at r^2 = 2/3
+        let model = RadialTangential::new(Pinhole::from_focal_and_principal_point(400.0, 400.0, 320.0, 240.0, 0.0), [-0.5, 0.0, 0.0, 0.0, 0.0]);
+        assert!(model.project(&Vector3::new(0.8, 0.0, 1.0)).is_some());
+        assert_eq!(model.project(&Vector3::new(0.9, 0.0, 1.0)), None);
+    }
+}
@@ -0,0 +1,65 @@
+extern crate nalgebra as na;
+
+use na::{Vector2,Vector3,Matrix2x3};
+use crate::float::Float;
+use crate::convention::Convention;
+use super::{CameraModel,DoubleSphere};
+
+pub const NAME: &str = "unified";
+
+/**
+ * Unified Camera Model in the alpha parametrization of Usenko et al.
+ * It is the Double Sphere model with xi = 0.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Unified<F: Float> {
+    double_sphere: DoubleSphere<F>
+}
+
+impl<F: Float> Unified<F> {
+    pub fn new(fx: F, fy: F, cx: F, cy: F, alpha: F) -> Unified<F> {
+        Unified {double_sphere: DoubleSphere::new(fx, fy, cx, cy, F::zero(), alpha)}
+    }
+
+    pub fn get_alpha(&self) -> F {self.double_sphere.get_alpha()}
+}
+
+impl<F: Float> CameraModel<F> for Unified<F> {
+    fn name(&self) -> &'static str {NAME}
+
+    fn project(&self, p_cam: &Vector3<F>) -> Option<Vector2<F>> {
+        self.double_sphere.project(p_cam)
+    }
+
+    fn unproject(&self, p_image: &Vector2<F>) -> Option<Vector3<F>> {
+        self.double_sphere.unproject(p_image)
+    }
+
+    fn jacobian(&self, p_cam: &Vector3<F>) -> Matrix2x3<F> {
+        self.double_sphere.jacobian(p_cam)
+    }
+
+    fn convention(&self) -> Convention {
+        self.double_sphere.convention()
+    }
+
+    fn flip_convention(&self, image_height: F) -> Unified<F> {
+        Unified {double_sphere: self.double_sphere.flip_convention(image_height)}
+    }
+
+    /**
+     * [fx, fy, cx, cy, alpha]
+     */
+    fn get_parameters(&self) -> Vec<F> {
+        let mut parameters = self.double_sphere.get_parameters();
+        parameters.remove(4);
+        parameters
+    }
+
+    fn from_parameters(name: &str, parameters: &[F]) -> Option<Unified<F>> {
+        match (name, parameters) {
+            (NAME, &[fx,fy,cx,cy,alpha]) if alpha >= F::zero() && alpha <= F::one() => Some(Unified::new(fx, fy, cx, cy, alpha)),
+            _ => None
+        }
+    }
+}
@@ -0,0 +1,92 @@
+extern crate nalgebra as na;
+
+use na::{Vector2,Vector3,Matrix2x3};
+use crate::float::Float;
+use crate::convention::Convention;
+use super::{CameraModel,DepthInterpolation,Orthographic};
+
+pub const NAME: &str = "weak_perspective";
+
+/**
+ * Scaled orthographic projection. All points are scaled as if they were at the reference depth:
+ * u = fx*x/reference_depth + cx, v = fy*y/reference_depth + cy.
+ * The sign of fx selects the viewing direction as for the pinhole.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct WeakPerspective<F: Float> {
+    fx: F,
+    fy: F,
+    reference_depth: F,
+    orthographic: Orthographic<F>
+}
+
+impl<F: Float> WeakPerspective<F> {
+    pub fn new(fx: F, fy: F, cx: F, cy: F, reference_depth: F) -> WeakPerspective<F> {
+        assert!(reference_depth > F::zero(), "reference depth has to be positive");
+        WeakPerspective {fx, fy, reference_depth, orthographic: Orthographic::new(fx/reference_depth, fy/reference_depth, cx, cy)}
+    }
+
+    pub fn get_reference_depth(&self) -> F {self.reference_depth}
+}
+
+impl<F: Float> CameraModel<F> for WeakPerspective<F> {
+    fn name(&self) -> &'static str {NAME}
+
+    fn project(&self, p_cam: &Vector3<F>) -> Option<Vector2<F>> {
+        self.orthographic.project(p_cam)
+    }
+
+    fn unproject(&self, p_image: &Vector2<F>) -> Option<Vector3<F>> {
+        self.orthographic.unproject(p_image)
+    }
+
+    fn jacobian(&self, p_cam: &Vector3<F>) -> Matrix2x3<F> {
+        self.orthographic.jacobian(p_cam)
+    }
+
+    fn depth(&self, p_cam: &Vector3<F>) -> F {
+        self.orthographic.depth(p_cam)
+    }
+
+    fn depth_interpolation(&self) -> DepthInterpolation {
+        DepthInterpolation::Affine
+    }
+
+    fn convention(&self) -> Convention {
+        self.orthographic.convention()
+    }
+
+    fn flip_convention(&self, image_height: F) -> WeakPerspective<F> {
+        let orthographic = self.orthographic.get_parameters();
+        WeakPerspective::new(-self.fx, -self.fy, orthographic[2], image_height - orthographic[3], self.reference_depth)
+    }
+
+    /**
+     * [fx, fy, cx, cy, reference_depth]
+     */
+    fn get_parameters(&self) -> Vec<F> {
+        let orthographic = self.orthographic.get_parameters();
+        vec![self.fx,self.fy,orthographic[2],orthographic[3],self.reference_depth]
+    }
+
+    fn from_parameters(name: &str, parameters: &[F]) -> Option<WeakPerspective<F>> {
+        match (name, parameters) {
+            (NAME, &[fx,fy,cx,cy,reference_depth]) if reference_depth > F::zero() => Some(WeakPerspective::new(fx, fy, cx, cy, reference_depth)),
+            _ => None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera_model::Pinhole;
+
+    #[test]
+    fn matches_pinhole_at_reference_depth() {
+        let model = WeakPerspective::new(-500.0, -500.0, 320.0, 240.0, 4.0);
+        let pinhole = Pinhole::from_focal_and_principal_point(-500.0, -500.0, 320.0, 240.0, 0.0);
+        let p = Vector3::new(0.7, -0.3, -4.0);
+        assert!((model.project(&p).unwrap() - pinhole.project(&p).unwrap()).norm() < 1e-9);
+    }
+}
@@ -0,0 +1,71 @@
+extern crate nalgebra as na;
+
+use na::{Vector3,Matrix3x4,Isometry3,UnitQuaternion};
+use serde::{Serialize, Deserialize};
+use crate::float::Float;
+
+/**
+ * Axes of the camera frame and orientation of the image rows.
+ * Converting between the conventions rotates the camera frame by PI around X and flips the image rows, v' = height - v.
+ * The world frame of the landmarks is the same in all conventions.
+ */
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Default,Serialize,Deserialize)]
+pub enum Convention {
+    /// OpenGL and glTF: +X right, +Y up, the camera looks along -Z, image rows increase upwards
+    #[default]
+    OpenGl,
+    /// OpenCV and COLMAP: +X right, +Y down, the camera looks along +Z, image rows increase downwards
+    OpenCv
+}
+
+impl Convention {
+    /**
+     * 1 if the camera looks along +Z, -1 if it looks along -Z.
+     * Camera models encode the viewing direction in the sign of their focal length.
+     */
+    pub fn forward_sign<F: Float>(&self) -> F {
+        match self {
+            Convention::OpenGl => -F::one(),
+            Convention::OpenCv => F::one()
+        }
+    }
+
+    pub fn from_forward_sign<F: Float>(forward_sign: F) -> Convention {
+        match forward_sign < F::zero() {
+            true => Convention::OpenGl,
+            false => Convention::OpenCv
+        }
+    }
+
+    /**
+     * Expresses a view matrix of this convention in the target convention
+     */
+    pub fn convert_view_matrix<F: Float>(&self, view_matrix: &Matrix3x4<F>, target: Convention) -> Matrix3x4<F> {
+        let mut converted = *view_matrix;
+        if *self != target {
+            converted.row_mut(1).neg_mut();
+            converted.row_mut(2).neg_mut();
+        }
+        converted
+    }
+
+    /**
+     * Expresses a transform from the camera frame of this convention, e.g. a pose or rig extrinsics, in the target convention
+     */
+    pub fn convert_camera_pose<F: Float>(&self, camera_to_frame: &Isometry3<F>, target: Convention) -> Isometry3<F> {
+        match *self == target {
+            true => *camera_to_frame,
+            false => camera_to_frame*UnitQuaternion::from_axis_angle(&Vector3::x_axis(), F::pi())
+        }
+    }
+
+    /**
+     * Expresses a camera frame vector of this convention in the target convention
+     */
+    pub fn convert_camera_vector<F: Float>(&self, v: &Vector3<F>, target: Convention) -> Vector3<F> {
+        match *self == target {
+            true => *v,
+            false => Vector3::new(v.x, -v.y, -v.z)
+        }
+    }
+}
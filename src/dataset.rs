@@ -0,0 +1,351 @@
+extern crate nalgebra as na;
+
+use std::fs;
+use std::path::Path;
+use na::Vector2;
+use crate::camera_features::CameraFeatures;
+use crate::landmark::Landmark;
+use crate::io::{self,Format};
+use crate::float::Float;
+use crate::convention::Convention;
+use crate::imu::ImuSample;
+use crate::perturbation::{Perturbation,Initialisation};
+use crate::schema::{DatasetManifest,MeshInfo,GenerationParameters,ImuFiles,InitialisationFiles,SCHEMA_VERSION};
+
+pub const MANIFEST_FILE_NAME: &str = "manifest.yaml";
+pub const IMU_DATA_FILE_NAME: &str = "imu0/data.csv";
+pub const IMU_GROUNDTRUTH_FILE_NAME: &str = "state_groundtruth_estimate0/data.csv";
+pub const INITIALISATION_DIR_NAME: &str = "initialisation";
+
+/**
+ * A complete ground truth set: landmarks, cameras with their intrinsics and resolution, the source mesh and how the views were generated.
+ * Optionally contains IMU measurements along the camera trajectory and a perturbed initialisation of the cameras and landmarks.
+ */
+#[derive(Debug,PartialEq)]
+pub struct Dataset<F: Float> {
+    landmarks: Vec<Landmark<F>>,
+    camera_features: Vec<CameraFeatures<F>>,
+    source_mesh: MeshInfo,
+    generation: GenerationParameters,
+    convention: Convention,
+    imu: Vec<ImuSample<F>>,
+    initialisation: Option<Initialisation<F>>
+}
+
+impl<F: Float> Dataset<F> {
+    /**
+     * All cameras have to share the same convention, see try_new
+     */
+    pub fn new(landmarks: Vec<Landmark<F>>, camera_features: Vec<CameraFeatures<F>>, source_mesh: MeshInfo, generation: GenerationParameters) -> Dataset<F> {
+        Self::try_new(landmarks, camera_features, source_mesh, generation).expect("All cameras of a dataset have to use the same convention")
+    }
+
+    /**
+     * InvalidData if the cameras use different conventions
+     */
+    pub fn try_new(landmarks: Vec<Landmark<F>>, camera_features: Vec<CameraFeatures<F>>, source_mesh: MeshInfo, generation: GenerationParameters) -> std::io::Result<Dataset<F>> {
+        let convention = Self::try_common_convention(&camera_features).ok_or_else(|| invalid_data("Cameras of the dataset use different conventions"))?;
+        Ok(Dataset {landmarks, camera_features, source_mesh, generation, convention, imu: vec![], initialisation: None})
+    }
+
+    pub fn get_landmarks(&self) -> &Vec<Landmark<F>> {&self.landmarks}
+    pub fn get_camera_features(&self) -> &Vec<CameraFeatures<F>> {&self.camera_features}
+    pub fn get_source_mesh(&self) -> &MeshInfo {&self.source_mesh}
+    pub fn get_generation_parameters(&self) -> &GenerationParameters {&self.generation}
+    pub fn get_convention(&self) -> Convention {self.convention}
+    pub fn get_imu(&self) -> &Vec<ImuSample<F>> {&self.imu}
+    pub fn get_initialisation(&self) -> Option<&Initialisation<F>> {self.initialisation.as_ref()}
+
+    pub fn with_imu(self, imu: Vec<ImuSample<F>>) -> Dataset<F> {
+        Dataset {imu, ..self}
+    }
+
+    /**
+     * Panics if the initialisation does not match the dataset, see try_with_initialisation
+     */
+    pub fn with_initialisation(self, initialisation: Option<Initialisation<F>>) -> Dataset<F> {
+        self.try_with_initialisation(initialisation).expect("Invalid initialisation")
+    }
+
+    /**
+     * Cameras of the initialisation have to use the convention of the dataset, see Perturbation::perturb_dataset.
+     * InvalidData if the convention or the camera and landmark ids differ from the ground truth.
+     */
+    pub fn try_with_initialisation(self, initialisation: Option<Initialisation<F>>) -> std::io::Result<Dataset<F>> {
+        if let Some(initialisation) = &initialisation {
+            let convention = Self::try_common_convention(initialisation.get_camera_features()).ok_or_else(|| invalid_data("Cameras of the initialisation use different conventions"))?;
+            if convention != self.convention {
+                return Err(invalid_data(format!("Initialisation convention {:?} differs from the dataset convention {:?}", convention, self.convention)));
+            }
+            let camera_ids = |cfs: &[CameraFeatures<F>]| cfs.iter().map(|cf| cf.get_cam_id()).collect::<Vec<_>>();
+            if camera_ids(initialisation.get_camera_features()) != camera_ids(&self.camera_features) {
+                return Err(invalid_data(format!("Initialisation has {} cameras, their ids differ from the {} ground truth cameras", initialisation.get_camera_features().len(), self.camera_features.len())));
+            }
+            let landmark_ids = |landmarks: &[Landmark<F>]| landmarks.iter().map(|l| *l.get_id()).collect::<Vec<_>>();
+            if landmark_ids(initialisation.get_landmarks()) != landmark_ids(&self.landmarks) {
+                return Err(invalid_data(format!("Initialisation has {} landmarks, their ids differ from the {} ground truth landmarks", initialisation.get_landmarks().len(), self.landmarks.len())));
+            }
+        }
+        Ok(Dataset {initialisation, ..self})
+    }
+
+    /**
+     * Converts all cameras to the target convention, see CameraFeatures::to_convention.
+     * Returns None if a camera can not be converted.
+     */
+    pub fn to_convention(&self, target: Convention) -> Option<Dataset<F>> {
+        let camera_features = self.camera_features.iter().map(|cf| cf.to_convention(target)).collect::<Option<Vec<_>>>()?;
+        let initialisation = match &self.initialisation {
+            Some(initialisation) => {
+                let camera_features = initialisation.get_camera_features().iter().map(|cf| cf.to_convention(target)).collect::<Option<Vec<_>>>()?;
+                Some(Initialisation::new(camera_features, initialisation.get_landmarks().clone(), *initialisation.get_perturbation()))
+            },
+            None => None
+        };
+        Some(Dataset {
+            landmarks: self.landmarks.clone(),
+            camera_features,
+            source_mesh: self.source_mesh.clone(),
+            generation: self.generation.clone(),
+            convention: target,
+            imu: self.imu.clone(),
+            initialisation
+        })
+    }
+
+    /**
+     * None if the cameras use different conventions
+     */
+    fn try_common_convention(camera_features: &[CameraFeatures<F>]) -> Option<Convention> {
+        let convention = camera_features.first().map_or(Convention::default(), |cf| cf.get_convention());
+        camera_features.iter().all(|cf| cf.get_convention() == convention).then_some(convention)
+    }
+
+    /**
+     * Writes the manifest, camera features and landmarks into dir_str, creating it if necessary.
+     * Optionally renders one png per camera with its visible features, which requires the resolution of every camera.
+     */
+    pub fn save(&self, dir_str: &str, format: Format, write_images: bool) -> std::io::Result<()> {
+        let dir = Path::new(dir_str);
+        fs::create_dir_all(dir)?;
+
+        let camera_features_file = format!("camera_features.{}", format.extension());
+        let landmarks_file = format!("landmarks.{}", format.extension());
+        io::serialize_feature_matches_with_format(&dir.join(&camera_features_file).to_string_lossy(), &self.camera_features, format)?;
+        io::serialize_landmarks_with_format(&dir.join(&landmarks_file).to_string_lossy(), &self.landmarks, format)?;
+
+        let images = match write_images {
+            true => self.write_images(dir)?,
+            false => vec![]
+        };
+
+        let imu = match self.imu.is_empty() {
+            true => None,
+            false => {
+                for file in [IMU_DATA_FILE_NAME, IMU_GROUNDTRUTH_FILE_NAME] {
+                    fs::create_dir_all(dir.join(file).parent().expect("IMU files are in a sub directory"))?;
+                }
+                io::write_euroc_imu(&dir.join(IMU_DATA_FILE_NAME).to_string_lossy(), &dir.join(IMU_GROUNDTRUTH_FILE_NAME).to_string_lossy(), &self.imu)?;
+                Some(ImuFiles {data: IMU_DATA_FILE_NAME.to_string(), groundtruth: IMU_GROUNDTRUTH_FILE_NAME.to_string(), sample_count: self.imu.len()})
+            }
+        };
+
+        let initialisation = match &self.initialisation {
+            Some(initialisation) => {
+                fs::create_dir_all(dir.join(INITIALISATION_DIR_NAME))?;
+                let files = InitialisationFiles {
+                    camera_features: format!("{}/{}", INITIALISATION_DIR_NAME, camera_features_file),
+                    landmarks: format!("{}/{}", INITIALISATION_DIR_NAME, landmarks_file),
+                    perturbation: initialisation.get_perturbation().to_serial()
+                };
+                io::serialize_feature_matches_with_format(&dir.join(&files.camera_features).to_string_lossy(), initialisation.get_camera_features(), format)?;
+                io::serialize_landmarks_with_format(&dir.join(&files.landmarks).to_string_lossy(), initialisation.get_landmarks(), format)?;
+                Some(files)
+            },
+            None => None
+        };
+
+        let manifest = DatasetManifest {
+            version: SCHEMA_VERSION,
+            format,
+            camera_features: camera_features_file,
+            landmarks: landmarks_file,
+            images,
+            camera_count: self.camera_features.len(),
+            landmark_count: self.landmarks.len(),
+            convention: Some(self.convention),
+            imu,
+            initialisation,
+            source_mesh: self.source_mesh.clone(),
+            generation: self.generation.clone()
+        };
+        let manifest_yaml = serde_yaml::to_string(&manifest).map_err(invalid_data)?;
+        fs::write(dir.join(MANIFEST_FILE_NAME), manifest_yaml)
+    }
+
+    pub fn load(dir_str: &str) -> std::io::Result<Dataset<F>> {
+        let dir = Path::new(dir_str);
+        let manifest_yaml = fs::read_to_string(dir.join(MANIFEST_FILE_NAME))?;
+        let manifest: DatasetManifest = serde_yaml::from_str(&manifest_yaml).map_err(invalid_data)?;
+        if manifest.version > SCHEMA_VERSION {
+            return Err(invalid_data(format!("Unsupported schema version {}, the latest supported version is {}", manifest.version, SCHEMA_VERSION)));
+        }
+
+        let camera_features = io::deserialize_feature_matches_with_format(&dir.join(&manifest.camera_features).to_string_lossy(), manifest.format)?;
+        let landmarks = io::deserialize_landmarks_with_format(&dir.join(&manifest.landmarks).to_string_lossy(), manifest.format)?;
+        if camera_features.len() != manifest.camera_count {
+            return Err(invalid_data(format!("Manifest lists {} cameras, the camera features file has {}", manifest.camera_count, camera_features.len())));
+        }
+        if landmarks.len() != manifest.landmark_count {
+            return Err(invalid_data(format!("Manifest lists {} landmarks, the landmarks file has {}", manifest.landmark_count, landmarks.len())));
+        }
+
+        let imu = match &manifest.imu {
+            Some(files) => io::read_euroc_imu(&dir.join(&files.data).to_string_lossy(), &dir.join(&files.groundtruth).to_string_lossy())?,
+            None => vec![]
+        };
+        let sample_count = manifest.imu.as_ref().map_or(0, |files| files.sample_count);
+        if imu.len() != sample_count {
+            return Err(invalid_data(format!("Manifest lists {} IMU samples, the IMU files have {}", sample_count, imu.len())));
+        }
+        let convention = Self::try_common_convention(&camera_features).ok_or_else(|| invalid_data("Cameras of the dataset use different conventions"))?;
+        if let Some(manifest_convention) = manifest.convention.filter(|c| *c != convention) {
+            return Err(invalid_data(format!("Manifest convention {:?} does not match the cameras ({:?})", manifest_convention, convention)));
+        }
+        let initialisation = match &manifest.initialisation {
+            Some(files) => Some(Initialisation::new(
+                io::deserialize_feature_matches_with_format(&dir.join(&files.camera_features).to_string_lossy(), manifest.format)?,
+                io::deserialize_landmarks_with_format(&dir.join(&files.landmarks).to_string_lossy(), manifest.format)?,
+                Perturbation::from_serial(&files.perturbation))),
+            None => None
+        };
+
+        Dataset {
+            landmarks,
+            camera_features,
+            source_mesh: manifest.source_mesh,
+            generation: manifest.generation,
+            convention,
+            imu,
+            initialisation: None
+        }.try_with_initialisation(initialisation)
+    }
+
+    /**
+     * Returns the image paths relative to dir. Fails for cameras without a resolution, e.g. loaded from the unversioned format
+     */
+    fn write_images(&self, dir: &Path) -> std::io::Result<Vec<String>> {
+        let image_dir = dir.join("images");
+        fs::create_dir_all(&image_dir)?;
+        self.camera_features.iter().map(|cf| {
+            let resolution = cf.get_resolution().ok_or_else(|| invalid_data(format!("Camera {} has no resolution, images require one", cf.get_cam_id())))?;
+            let screen_points = cf.get_feature_map().values().map(|f| *f.get_pixel()).collect::<Vec<Vector2<usize>>>();
+            let data_vec = io::calculate_rgb_byte_vec(&screen_points, resolution.get_width(), resolution.get_height());
+            let image_file = format!("images/camera_features_{}.png", cf.get_cam_id());
+            io::write_png_data_to_file(&dir.join(&image_file).to_string_lossy(), &data_vec, resolution.get_width() as u32, resolution.get_height() as u32)?;
+            Ok(image_file)
+        }).collect()
+    }
+}
+
+fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(error: E) -> std::io::Error {
+    std::io::Error::new(std::io::ErrorKind::InvalidData, error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::{BTreeMap,HashMap};
+    use na::{Vector3,Matrix3x4};
+    use crate::camera::Resolution;
+    use crate::camera_model::pinhole::Pinhole;
+    use crate::feature::Feature;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::filter::FilterType;
+    use crate::test_util::TempPath;
+
+    fn dataset() -> Dataset<f64> {
+        let model = Pinhole::from_focal_and_principal_point(300.0, 300.0, 32.0, 24.0, 0.0);
+        let landmarks = vec![Landmark::new(&0, &Vector3::new(0.0, 0.0, -5.0)), Landmark::new(&1, &Vector3::new(1.0, -1.0, -4.0))];
+        let camera_features = (0..2).map(|cam_id| {
+            let view_matrix = Matrix3x4::new(
+                1.0, 0.0, 0.0, -0.5*cam_id as f64,
+                0.0, 1.0, 0.0, 0.0,
+                0.0, 0.0, 1.0, 0.0);
+            let feature_map = HashMap::from([(0, Feature::new(&Vector2::new(32.5 - 10.0*cam_id as f64, 24.25)))]);
+            CameraFeatures::new(feature_map, cam_id, view_matrix, &model, Resolution::new(64, 48))
+        }).collect();
+        let source_mesh = MeshInfo {name: "test".to_string(), path: None, vertex_count: 2};
+        let generation = GenerationParameters {filter_type: FilterType::Depth, extras: BTreeMap::new()};
+        Dataset::new(landmarks, camera_features, source_mesh, generation)
+    }
+
+    #[test]
+    fn save_and_load() {
+        for format in [Format::Yaml, Format::Json, Format::Binary] {
+            let dir = TempPath::new(&format!("round_trip_{}", format.extension()));
+            dataset().save(&dir, format, true).unwrap();
+            assert!(Path::new(&*dir).join("images/camera_features_1.png").exists());
+            let loaded = Dataset::<f64>::load(&dir);
+            assert_eq!(loaded.unwrap(), dataset());
+        }
+    }
+
+    #[test]
+    fn newer_manifest_version_is_rejected() {
+        let dir = TempPath::new("newer_manifest");
+        dataset().save(&dir, Format::Yaml, false).unwrap();
+        let manifest_path = Path::new(&*dir).join(MANIFEST_FILE_NAME);
+        let manifest = fs::read_to_string(&manifest_path).unwrap().replace(&format!("version: {}", SCHEMA_VERSION), &format!("version: {}", SCHEMA_VERSION+1));
+        fs::write(&manifest_path, manifest).unwrap();
+        let error = Dataset::<f64>::load(&dir).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn count_mismatch_is_rejected() {
+        let dir = TempPath::new("count_mismatch");
+        dataset().save(&dir, Format::Yaml, false).unwrap();
+        let manifest_path = Path::new(&*dir).join(MANIFEST_FILE_NAME);
+        let manifest = fs::read_to_string(&manifest_path).unwrap().replace("camera_count: 2", "camera_count: 3");
+        fs::write(&manifest_path, manifest).unwrap();
+        let error = Dataset::<f64>::load(&dir).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn images_require_a_resolution() {
+        let dataset = dataset();
+        let mut serial = dataset.get_camera_features()[0].to_camera_serial();
+        serial.resolution = None;
+        let camera_features = vec![CameraFeatures::from_camera_serial(&serial)];
+        let dataset = Dataset::new(dataset.landmarks, camera_features, dataset.source_mesh, dataset.generation);
+        let dir = TempPath::new("no_resolution");
+        let result = dataset.save(&dir, Format::Yaml, true);
+        assert_eq!(result.unwrap_err().kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn mixed_conventions_are_rejected() {
+        let dataset = dataset();
+        let mut camera_features = dataset.camera_features.clone();
+        camera_features[1] = camera_features[1].to_convention(Convention::OpenGl).unwrap();
+        let error = Dataset::try_new(dataset.landmarks, camera_features, dataset.source_mesh, dataset.generation).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn mismatched_initialisation_is_rejected() {
+        let initialisation = Perturbation::new().translation_sigma(0.1).perturb_dataset(&dataset(), &mut StdRng::seed_from_u64(0));
+        let (camera_features, landmarks) = (initialisation.get_camera_features(), initialisation.get_landmarks());
+        let mut renumbered = landmarks.clone();
+        renumbered[1] = Landmark::new(&7, renumbered[1].get_position());
+        for (camera_features, landmarks) in [(camera_features[..1].to_vec(), landmarks.clone()), (camera_features.clone(), landmarks[..1].to_vec()), (camera_features.clone(), renumbered)] {
+            let mismatched = Initialisation::new(camera_features, landmarks, *initialisation.get_perturbation());
+            let error = dataset().try_with_initialisation(Some(mismatched)).unwrap_err();
+            assert_eq!(error.kind(), std::io::ErrorKind::InvalidData);
+        }
+        assert_eq!(dataset().try_with_initialisation(Some(initialisation.clone())).unwrap().get_initialisation(), Some(&initialisation));
+    }
+}
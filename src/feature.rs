@@ -0,0 +1,54 @@
+extern crate nalgebra as na;
+
+use na::{Vector2,Vector3};
+use crate::float::{self,Float};
+
+/**
+ * A projected landmark. The position is the exact projection in image space,
+ * the pixel is the cell which was used to resolve occlusions.
+ * The bearing is the unit vector towards the landmark in the camera frame, if known.
+ * The timestamp is the capture time of the feature for cameras that do not expose all pixels at once.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Feature<F: Float> {
+    position: Vector2<F>,
+    pixel: Vector2<usize>,
+    bearing: Option<Vector3<F>>,
+    timestamp: Option<F>
+}
+
+impl<F: Float> Feature<F> {
+    /**
+     * The pixel is the cell containing the position
+     */
+    pub fn new(position: &Vector2<F>) -> Feature<F> {
+        Feature {
+            position: *position,
+            pixel: Vector2::new(float::to_usize(position.x.floor()), float::to_usize(position.y.floor())),
+            bearing: None,
+            timestamp: None
+        }
+    }
+
+    pub fn from_parts(position: &Vector2<F>, pixel: &Vector2<usize>) -> Feature<F> {
+        Feature {
+            position: *position,
+            pixel: *pixel,
+            bearing: None,
+            timestamp: None
+        }
+    }
+
+    pub fn with_bearing(self, bearing: Option<Vector3<F>>) -> Feature<F> {
+        Feature {bearing, ..self}
+    }
+
+    pub fn with_timestamp(self, timestamp: Option<F>) -> Feature<F> {
+        Feature {timestamp, ..self}
+    }
+
+    pub fn get_position(&self) -> &Vector2<F> {&self.position}
+    pub fn get_pixel(&self) -> &Vector2<usize> {&self.pixel}
+    pub fn get_bearing(&self) -> Option<&Vector3<F>> {self.bearing.as_ref()}
+    pub fn get_timestamp(&self) -> Option<F> {self.timestamp}
+}
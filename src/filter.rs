@@ -1,82 +1,67 @@
 extern crate nalgebra as na;
 
-use na::{Vector2, Matrix3xX};
+use na::{Vector2, Vector3};
 use std::collections::HashMap;
 use crate::triangle::Triangle;
+use crate::feature::Feature;
+use crate::float::{self,Float};
 use crate::rasterizer;
+use crate::ProjectedPoint;
+use crate::camera_model::DepthInterpolation;
+use serde::{Serialize, Deserialize};
 
-
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Serialize,Deserialize)]
 pub enum FilterType {
     Depth,
     Rasterizer
 }
 
-pub fn filter_visible_screen_points_by_depth(screen_points_with_index: &Vec<(usize,Vector2<f32>)>, points_cam: &Matrix3xX<f32>,) -> Vec<(usize,Vector2<usize>)> {
+/**
+ * Keeps the closest point of every pixel. Screen points and depths are aligned, None marks a point that could not be projected.
+ * Depths are defined by the camera model, smaller is closer.
+ */
+pub fn filter_visible_screen_points_by_depth<F: Float>(screen_points_with_index: &[ProjectedPoint<F>], depths: &[F]) -> Vec<(usize,Feature<F>)> {
+    assert_eq!(screen_points_with_index.len(), depths.len());
     let mut closest_point_map = HashMap::<(usize,usize), usize>::with_capacity(screen_points_with_index.len());
-    for (i,&(global_id,screen_p)) in screen_points_with_index.iter().filter(|(_,p)| p.x > 0.0 && p.y >= 0.0).enumerate() {
-        let key = (screen_p.x.floor() as usize,screen_p.y.floor() as usize);
-        match closest_point_map.contains_key(&key) {
-            true => {
-                let current_point_index = closest_point_map.get(&key).unwrap();
-                let current_point_depth = points_cam[*current_point_index];
-                let depth = points_cam[(2,global_id)];
-                // GLTF models are displayed along the negative Z-Axis
-                if depth > current_point_depth  {
-                    closest_point_map.insert(key, i);
-                }
-            },
-            false => {closest_point_map.insert(key, i);()}
+    let projected_points = screen_points_with_index.iter().enumerate()
+        .filter_map(|(i,p)| p.map(|(_,screen_p)| (i,screen_p)))
+        .filter(|(_,p)| p.x >= F::zero() && p.y >= F::zero());
+    for (i,screen_p) in projected_points {
+        let key = (float::to_usize(screen_p.x.floor()),float::to_usize(screen_p.y.floor()));
+        match closest_point_map.get(&key) {
+            Some(&current_point_index) if depths[current_point_index] <= depths[i] => (),
+            _ => {closest_point_map.insert(key, i);}
         }
     }
-    closest_point_map.into_values().map(|i| screen_points_with_index[i]).map(|(i,v)| (i, Vector2::new(v.x.floor() as usize, v.y.floor() as usize))).collect()
+    closest_point_map.into_values()
+        .filter_map(|i| screen_points_with_index[i])
+        .map(|(i,v)| (i, Feature::new(&v))).collect()
 }
 
-pub fn filter_visible_screen_points_by_rasterizer(screen_cam_triangles: &Vec<(Triangle<2>,Triangle<3>)>, screen_width: f32, screen_height: f32) -> Vec<(usize,Vector2<usize>)> {
-    assert!(screen_height.fract() <= f32::EPSILON);
-    assert!(screen_width.fract() <= f32::EPSILON);
-    let mut depth_buffer = HashMap::<(usize,usize),(f32,Option<(usize,Vector2<usize>)>)>::with_capacity((screen_height*screen_width) as usize);
-    for (tri_2d,tri_3d) in screen_cam_triangles.iter() {
-        let barycentric_coordiantes_with_pixel = rasterizer::calc_all_pixels_within_triangle(tri_2d);
+/**
+ * Each screen triangle is paired with the model depths of its vertices.
+ */
+pub fn filter_visible_screen_points_by_rasterizer<F: Float>(screen_triangles_with_depths: &[(Triangle<F,2>,Vector3<F>)], depth_interpolation: DepthInterpolation, screen_width: usize, screen_height: usize) -> Vec<(usize,Feature<F>)> {
+    // Stores the interpolated depth, smaller is closer
+    let mut depth_buffer = HashMap::<(usize,usize),(F,Option<(usize,Feature<F>)>)>::with_capacity(screen_height*screen_width);
+    for (tri_2d,vertex_depths) in screen_triangles_with_depths.iter() {
+        let barycentric_coordiantes_with_pixel = rasterizer::calc_all_pixels_within_triangle(tri_2d,screen_width,screen_height);
         let barycentric_coordiantes = barycentric_coordiantes_with_pixel.iter().map(|(w0,w1,w2,_)| (*w0,*w1,*w2)).collect::<Vec<_>>();
-        let pixel_depths = rasterizer::calc_inv_z_for_all_pixels(&barycentric_coordiantes,tri_3d);
-        let mut triangle_association_map = HashMap::<(usize,usize),usize>::with_capacity(3);
-        triangle_association_map.insert((tri_2d.get_v0().x.floor() as usize,tri_2d.get_v0().y.floor() as usize), tri_2d.get_id0().expect("Expected id for v0!"));
-        triangle_association_map.insert((tri_2d.get_v1().x.floor() as usize,tri_2d.get_v1().y.floor() as usize), tri_2d.get_id1().expect("Expected id for v1!"));
-        triangle_association_map.insert((tri_2d.get_v2().x.floor() as usize,tri_2d.get_v2().y.floor() as usize), tri_2d.get_id2().expect("Expected id for v2!"));
-        for i in 0..pixel_depths.len() {
-            let depth = pixel_depths[i];
-            assert!(depth < 0.0);
-            let pixel = barycentric_coordiantes_with_pixel[i].3;
-            let key = (pixel.x.floor() as usize,pixel.y.floor() as usize);
+        let pixel_depths = rasterizer::calc_depth_for_all_pixels(&barycentric_coordiantes,vertex_depths,depth_interpolation);
+        // Maps the pixel cell of a vertex to its id and exact projected position. Vertices off screen are not associated
+        let triangle_association_map = [(tri_2d.get_id0(),tri_2d.get_v0()),(tri_2d.get_id1(),tri_2d.get_v1()),(tri_2d.get_id2(),tri_2d.get_v2())].into_iter()
+            .filter(|(_,v)| v.x >= F::zero() && v.y >= F::zero())
+            .map(|(id,v)| ((float::to_usize(v.x.floor()),float::to_usize(v.y.floor())), (id.expect("Expected id for vertex!"),v)))
+            .collect::<HashMap<(usize,usize),(usize,Vector2<F>)>>();
+        for (&depth,&(_,_,_,pixel)) in pixel_depths.iter().zip(barycentric_coordiantes_with_pixel.iter()) {
+            let key = (float::to_usize(pixel.x.floor()),float::to_usize(pixel.y.floor()));
             let pixel_u = Vector2::new(key.0,key.1);
-            let pixel_is_vertex = triangle_association_map.contains_key(&key);
-            match (depth_buffer.contains_key(&key), pixel_is_vertex) {
-                (false,false) => {
-                    depth_buffer.insert(key.clone(), (depth,None));
-                    ()
-                },
-                (false,true) => {
-                    depth_buffer.insert(key.clone(), (depth,Some((*triangle_association_map.get(&key).unwrap(),pixel_u))));
-                    ()
-                },
-                (true,false) => {
-                    let &(current_depth,_v) = depth_buffer.get(&key).unwrap();
-                    assert!(current_depth < 0.0);
-                    // GLTF is defined along -Z
-                    if depth > current_depth {
-                        depth_buffer.insert(key.clone(), (depth,None));
-                    }
-                },
-                (true,true) => {
-                    let &(current_depth,_v) = depth_buffer.get(&key).unwrap();
-                    assert!(current_depth < 0.0);
-                    // GLTF is defined along -Z
-                    if depth > current_depth {
-                        depth_buffer.insert(key.clone(), (depth,Some((*triangle_association_map.get(&key).unwrap(),pixel_u))));
-                    }
-                }
+            let vertex_feature = triangle_association_map.get(&key).map(|&(id,position)| (id,Feature::from_parts(&position,&pixel_u)));
+            match depth_buffer.get(&key) {
+                Some(&(current_depth,_)) if current_depth <= depth => (),
+                _ => {depth_buffer.insert(key, (depth,vertex_feature));}
             }
-        }      
+        }
     }
-    depth_buffer.values().into_iter().filter(|(_, some_v)| some_v.is_some()).map(|(_,some_v)|some_v.unwrap()).collect()
-}
\ No newline at end of file
+    depth_buffer.into_values().filter_map(|(_, some_v)| some_v).collect()
+}
@@ -0,0 +1,43 @@
+extern crate nalgebra as na;
+
+use std::str::FromStr;
+use na::RealField;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+
+/**
+ * Scalar type of all geometry in the crate. Implemented for f32 and f64.
+ */
+pub trait Float: RealField + Copy + Serialize + DeserializeOwned + FromStr {
+    /**
+     * Distinguishes binary files written with different precision
+     */
+    const BINARY_MAGIC: [u8;4];
+}
+
+impl Float for f32 {
+    const BINARY_MAGIC: [u8;4] = *b"MCV\x04";
+}
+
+impl Float for f64 {
+    const BINARY_MAGIC: [u8;4] = *b"MCV\x08";
+}
+
+pub fn from_f64<F: Float>(v: f64) -> F {
+    na::convert::<f64,F>(v)
+}
+
+pub fn to_f64<F: Float>(v: F) -> f64 {
+    na::try_convert::<F,f64>(v).expect("Float is not representable as f64")
+}
+
+/**
+ * Truncating conversion for pixel indices. Negative values saturate to 0
+ */
+pub fn to_usize<F: Float>(v: F) -> usize {
+    to_f64(v) as usize
+}
+
+pub fn from_usize<F: Float>(v: usize) -> F {
+    from_f64(v as f64)
+}
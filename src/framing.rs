@@ -0,0 +1,170 @@
+extern crate nalgebra as na;
+
+use na::{Vector2,Vector3,Point3,Isometry3};
+use crate::camera::CameraDescription;
+use crate::camera_model::CameraModel;
+use crate::float::{self,Float};
+use crate::trajectory;
+
+/**
+ * Axis aligned bounding box
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct BoundingBox<F: Float> {
+    min: Point3<F>,
+    max: Point3<F>
+}
+
+impl<F: Float> BoundingBox<F> {
+    pub fn new(min: Point3<F>, max: Point3<F>) -> BoundingBox<F> {
+        assert!(min.x <= max.x && min.y <= max.y && min.z <= max.z, "min has to be smaller than max");
+        BoundingBox {min, max}
+    }
+
+    pub fn from_points(points: &[Vector3<F>]) -> BoundingBox<F> {
+        assert!(!points.is_empty(), "Bounding box needs at least one point");
+        let (min,max) = points.iter().fold((points[0],points[0]), |(min,max),p| (min.inf(p),max.sup(p)));
+        BoundingBox::new(Point3::from(min), Point3::from(max))
+    }
+
+    pub fn get_min(&self) -> &Point3<F> {&self.min}
+    pub fn get_max(&self) -> &Point3<F> {&self.max}
+
+    pub fn center(&self) -> Point3<F> {
+        na::center(&self.min, &self.max)
+    }
+
+    /**
+     * Sphere through the corners of the box
+     */
+    pub fn bounding_sphere(&self) -> BoundingSphere<F> {
+        BoundingSphere::new(self.center(), (self.max - self.min).norm()/float::from_f64(2.0))
+    }
+}
+
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct BoundingSphere<F: Float> {
+    center: Point3<F>,
+    radius: F
+}
+
+impl<F: Float> BoundingSphere<F> {
+    pub fn new(center: Point3<F>, radius: F) -> BoundingSphere<F> {
+        assert!(radius >= F::zero(), "radius has to be non negative");
+        BoundingSphere {center, radius}
+    }
+
+    /**
+     * Sphere around the center of the bounding box of the points, containing all points
+     */
+    pub fn from_points(points: &[Vector3<F>]) -> BoundingSphere<F> {
+        let center = BoundingBox::from_points(points).center();
+        let radius = points.iter().map(|p| (p - center.coords).norm()).fold(F::zero(), |a,b| a.max(b));
+        BoundingSphere::new(center, radius)
+    }
+
+    pub fn get_center(&self) -> &Point3<F> {&self.center}
+    pub fn get_radius(&self) -> F {self.radius}
+}
+
+/**
+ * Smallest full field of view of the camera in radians, measured from the optical axis to the midpoints of the image borders.
+ * Returns None if a border midpoint can not be unprojected.
+ */
+pub fn field_of_view<F: Float, C: CameraModel<F>>(camera: &CameraDescription<F,C>) -> Option<F> {
+    let resolution = camera.get_resolution();
+    let (width, height) = (float::from_usize::<F>(resolution.get_width()), float::from_usize::<F>(resolution.get_height()));
+    let two = float::from_f64::<F>(2.0);
+    let optical_axis = Vector3::z()*camera.get_camera_model().convention().forward_sign::<F>();
+    let border_midpoints = [Vector2::new(F::zero(), height/two), Vector2::new(width, height/two), Vector2::new(width/two, F::zero()), Vector2::new(width/two, height)];
+    border_midpoints.iter()
+        .map(|p| camera.get_camera_model().unproject(p).map(|bearing| bearing.angle(&optical_axis)))
+        .try_fold(F::pi(), |min, angle| angle.map(|a| min.min(a)))
+        .map(|half_fov| two*half_fov)
+}
+
+/**
+ * Target and eye distance such that a bounding sphere fills a fraction of a perspective camera's image.
+ * The fill ratio is the diameter of the sphere's silhouette relative to the image extent of the field of view.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Framing<F: Float> {
+    target: Point3<F>,
+    distance: F
+}
+
+impl<F: Float> Framing<F> {
+    /**
+     * field_of_view in (0, PI) radians, fill_ratio in (0, 1]
+     */
+    pub fn new(sphere: &BoundingSphere<F>, field_of_view: F, fill_ratio: F) -> Framing<F> {
+        assert!(field_of_view > F::zero() && field_of_view < F::pi(), "field of view has to be in (0, PI)");
+        assert!(fill_ratio > F::zero() && fill_ratio <= F::one(), "fill ratio has to be in (0, 1]");
+        // The silhouette of a sphere at distance d has the angular radius asin(r/d)
+        let angular_radius = (fill_ratio*(field_of_view/float::from_f64(2.0)).tan()).atan();
+        Framing {target: sphere.center, distance: sphere.radius/angular_radius.sin()}
+    }
+
+    /**
+     * Frames the bounding sphere of the points with the field of view of the camera
+     */
+    pub fn for_camera<C: CameraModel<F>>(points: &[Vector3<F>], camera: &CameraDescription<F,C>, fill_ratio: F) -> Option<Framing<F>> {
+        let field_of_view = field_of_view(camera)?.min(F::pi() - F::default_epsilon().sqrt());
+        Some(Framing::new(&BoundingSphere::from_points(points), field_of_view, fill_ratio))
+    }
+
+    pub fn get_target(&self) -> &Point3<F> {&self.target}
+    pub fn get_distance(&self) -> F {self.distance}
+
+    /**
+     * Eye position at the framing distance from the target in the given direction
+     */
+    pub fn eye_along(&self, direction: &Vector3<F>) -> Point3<F> {
+        self.target + direction.normalize()*self.distance
+    }
+
+    /**
+     * Moves every camera to pose (camera to world, OpenGL) along the line through the target so that it is at the framing distance,
+     * and turns it towards the target keeping its up direction where possible
+     */
+    pub fn reframe(&self, poses: &[Isometry3<F>]) -> Vec<Isometry3<F>> {
+        poses.iter().map(|pose| {
+            let eye = Point3::from(pose.translation.vector);
+            let direction = (eye - self.target).try_normalize(F::zero()).unwrap_or_else(|| pose*Vector3::z());
+            trajectory::look_at(&self.eye_along(&direction), &self.target, &(pose*Vector3::y()))
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::{Camera,Resolution};
+    use crate::camera_model::Pinhole;
+
+    #[test]
+    fn silhouette_fills_the_image() {
+        let description = CameraDescription::new(Pinhole::from_focal_and_principal_point(-400.0, -400.0, 320.0, 240.0, 0.0), Resolution::new(640, 480));
+        let (center, radius) = (Vector3::new(1.0, -2.0, 0.5), 1.5f64);
+        let points = [Vector3::x(), -Vector3::x(), Vector3::y(), -Vector3::y(), Vector3::z(), -Vector3::z()].map(|axis| center + axis*radius);
+        for fill_ratio in [0.25, 0.6, 1.0] {
+            let framing = Framing::for_camera(&points, &description, fill_ratio).unwrap();
+            let eye = framing.eye_along(&Vector3::new(1.0, 0.5, 2.0));
+            let camera = Camera::new(0, "framed", trajectory::look_at(&eye, framing.get_target(), &Vector3::y()), description);
+
+            // Points where the viewing rays touch the sphere at the top and bottom of the image
+            let axis = (framing.get_target() - eye)/framing.get_distance();
+            let image_up = camera.get_pose()*Vector3::y();
+            let half_angle = (radius/framing.get_distance()).asin();
+            let tangent_length = (framing.get_distance().powi(2) - radius*radius).sqrt();
+            let silhouette = [1.0, -1.0].map(|sign| {
+                let tangent_point = eye + (axis*half_angle.cos() + image_up*(sign*half_angle.sin()))*tangent_length;
+                assert!(((tangent_point.coords - center).norm() - radius).abs() < 1e-9);
+                description.get_camera_model().project(&(camera.get_view()*tangent_point).coords).unwrap()
+            });
+
+            assert!(((silhouette[0] - silhouette[1]).norm()/480.0 - fill_ratio).abs() < 1e-9);
+            assert!(((silhouette[0] + silhouette[1])/2.0 - Vector2::new(320.0, 240.0)).norm() < 1e-9);
+        }
+    }
+}
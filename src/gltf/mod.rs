@@ -1,37 +1,46 @@
 extern crate nalgebra as na;
 mod byte_array_info;
 
+use std::io::{Error,ErrorKind};
 use na::Vector3;
 use byte_array_info::ByteArrayInfo;
+use gltf::accessor::{DataType,Dimensions};
+use crate::float::{self,Float};
 
 /**
  * Returns a Vec<ByteArrayInfo> of position data
  */
 fn find_position_buffer_data(document: &gltf::Document) -> Vec<ByteArrayInfo> { 
-    document.meshes().map(|mesh| {
-        mesh.primitives().map(|primitive| {
+    document.meshes().flat_map(|mesh| {
+        mesh.primitives().flat_map(|primitive| {
             primitive.attributes().filter(|attribute| attribute.0 == gltf::Semantic::Positions).map(|attr| {
                 let buffer_view = attr.1.view().expect("Buffer is sparse. This is not implemented");
                 ByteArrayInfo::new (attr.1.data_type(),attr.1.dimensions(), buffer_view.buffer().index(),buffer_view.offset(), buffer_view.length(), buffer_view.stride())
-            })
-        }).flatten()
-    }).flatten().collect()
+            }).collect::<Vec<_>>()
+        })
+    }).collect()
 }
 
-fn load_position_byte_data(position_buffer_info: Vec<ByteArrayInfo>, buffers: &Vec<gltf::buffer::Data>) -> Vec<&[u8]> {
+/**
+ * Fails for position accessors which are not float vec3, as required by the glTF 2.0 spec 3.7.2.1
+ */
+fn load_position_byte_data(position_buffer_info: Vec<ByteArrayInfo>, buffers: &[gltf::buffer::Data]) -> std::io::Result<Vec<&[u8]>> {
     position_buffer_info.into_iter().map(|info| {
+        if info.get_data_type() != DataType::F32 || info.get_data_dimension() != Dimensions::Vec3 {
+            return Err(Error::new(ErrorKind::InvalidData, format!("Positions have to be F32 Vec3, found {:?} {:?}", info.get_data_type(), info.get_data_dimension())));
+        }
         let byte_end = info.get_byte_offset()+info.get_byte_length();
         match info.get_byte_stride() {
-            None => &buffers[info.get_buffer_index()].0[info.get_byte_offset()..byte_end],
+            None => Ok(&buffers[info.get_buffer_index()].0[info.get_byte_offset()..byte_end]),
             Some(_) => panic!("TODO Strided Position Loading") // After every read, continue for stide - size bytes
         }
     }).collect()
 }
 
-fn convert_byte_data_to_vec3(position_byte_data: Vec<&[u8]>) -> Vec<Vec<Vector3<f32>>> {
+fn convert_byte_data_to_vec3<F: Float>(position_byte_data: Vec<&[u8]>) -> Vec<Vec<Vector3<F>>> {
     position_byte_data.into_iter().map(|byte_slice| {
         let vec3_capacity = byte_slice.len() / 12;
-        let mut data_vector = Vec::<Vector3<f32>>::with_capacity(vec3_capacity);
+        let mut data_vector = Vec::<Vector3<F>>::with_capacity(vec3_capacity);
         for i in 0..vec3_capacity {
             let x_s = 12*i;
             let x_f = x_s + 4;
@@ -45,7 +54,7 @@ fn convert_byte_data_to_vec3(position_byte_data: Vec<&[u8]>) -> Vec<Vec<Vector3<
             let x = f32::from_le_bytes(x_raw_arr);
             let y = f32::from_le_bytes(y_raw_arr);
             let z = f32::from_le_bytes(z_raw_arr);
-            data_vector.push(Vector3::<f32>::new(x, y, z));
+            data_vector.push(Vector3::<F>::new(float::from_f64(x as f64), float::from_f64(y as f64), float::from_f64(z as f64)));
         }
         data_vector
     }).collect()
@@ -56,12 +65,12 @@ pub fn load(path: &str) -> (gltf::Document, Vec<gltf::buffer::Data>) {
     (document, buffers)
 }
 
-pub fn load_vertex_positions(document: &gltf::Document, buffers: &Vec<gltf::buffer::Data>) -> Vec<Vec<Vector3<f32>>> {
-    let position_buffer_info = find_position_buffer_data(&document);
-    let positions_byte_data = load_position_byte_data(position_buffer_info, &buffers);
-    convert_byte_data_to_vec3(positions_byte_data)
+pub fn load_vertex_positions<F: Float>(document: &gltf::Document, buffers: &[gltf::buffer::Data]) -> std::io::Result<Vec<Vec<Vector3<F>>>> {
+    let position_buffer_info = find_position_buffer_data(document);
+    let positions_byte_data = load_position_byte_data(position_buffer_info, buffers)?;
+    Ok(convert_byte_data_to_vec3(positions_byte_data))
 }
 
 pub fn load_mesh_names(document: &gltf::Document) -> Vec<String> {
-    document.meshes().into_iter().map(|m| m.name().expect("no name for mesh").to_string()).collect::<Vec<String>>()
+    document.meshes().map(|m| m.name().expect("no name for mesh").to_string()).collect::<Vec<String>>()
 }
\ No newline at end of file
@@ -0,0 +1,238 @@
+extern crate nalgebra as na;
+
+use na::{Vector3,Isometry3};
+use rand::Rng;
+use rand_distr::StandardNormal;
+use crate::float::{self,Float};
+use crate::spline::KinematicTrajectory;
+
+const NANOSECONDS_PER_SECOND: f64 = 1e9;
+
+/**
+ * Continuous time noise model in the units of Kalibr and EuRoC.
+ * Noise densities are the white noise of the measurements, random walks drive the biases.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct ImuNoise<F: Float> {
+    /// rad/s/sqrt(Hz)
+    pub gyroscope_noise_density: F,
+    /// rad/s^2/sqrt(Hz)
+    pub gyroscope_random_walk: F,
+    /// m/s^2/sqrt(Hz)
+    pub accelerometer_noise_density: F,
+    /// m/s^3/sqrt(Hz)
+    pub accelerometer_random_walk: F
+}
+
+impl<F: Float> ImuNoise<F> {
+    pub fn noiseless() -> ImuNoise<F> {
+        ImuNoise {gyroscope_noise_density: F::zero(), gyroscope_random_walk: F::zero(), accelerometer_noise_density: F::zero(), accelerometer_random_walk: F::zero()}
+    }
+}
+
+/**
+ * IMU sampling, mounting and noise. Defaults to a noiseless IMU at the body origin without biases,
+ * no time offset and gravity along -Y (Y up world as in glTF).
+ * The time offset is added to the true time of a sample to get its IMU timestamp.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct ImuParameters<F: Float> {
+    rate: F,
+    gravity: Vector3<F>,
+    time_offset: F,
+    imu_to_body: Isometry3<F>,
+    noise: ImuNoise<F>,
+    initial_gyroscope_bias: Vector3<F>,
+    initial_accelerometer_bias: Vector3<F>
+}
+
+impl<F: Float> ImuParameters<F> {
+    /**
+     * rate in Hz
+     */
+    pub fn new(rate: F) -> ImuParameters<F> {
+        assert!(rate > F::zero(), "IMU rate has to be positive");
+        ImuParameters {
+            rate,
+            gravity: Vector3::new(F::zero(), float::from_f64(-9.81), F::zero()),
+            time_offset: F::zero(),
+            imu_to_body: Isometry3::identity(),
+            noise: ImuNoise::noiseless(),
+            initial_gyroscope_bias: Vector3::zeros(),
+            initial_accelerometer_bias: Vector3::zeros()
+        }
+    }
+
+    /**
+     * Gravity acceleration in the world frame
+     */
+    pub fn gravity(self, gravity: Vector3<F>) -> ImuParameters<F> {
+        ImuParameters {gravity, ..self}
+    }
+
+    pub fn time_offset(self, time_offset: F) -> ImuParameters<F> {
+        ImuParameters {time_offset, ..self}
+    }
+
+    /**
+     * Mounting of the IMU relative to the body frame of the trajectory
+     */
+    pub fn imu_to_body(self, imu_to_body: Isometry3<F>) -> ImuParameters<F> {
+        ImuParameters {imu_to_body, ..self}
+    }
+
+    pub fn noise(self, noise: ImuNoise<F>) -> ImuParameters<F> {
+        ImuParameters {noise, ..self}
+    }
+
+    pub fn initial_biases(self, gyroscope_bias: Vector3<F>, accelerometer_bias: Vector3<F>) -> ImuParameters<F> {
+        ImuParameters {initial_gyroscope_bias: gyroscope_bias, initial_accelerometer_bias: accelerometer_bias, ..self}
+    }
+
+    pub fn get_rate(&self) -> F {self.rate}
+    pub fn get_gravity(&self) -> &Vector3<F> {&self.gravity}
+    pub fn get_time_offset(&self) -> F {self.time_offset}
+    pub fn get_imu_to_body(&self) -> &Isometry3<F> {&self.imu_to_body}
+    pub fn get_noise(&self) -> &ImuNoise<F> {&self.noise}
+
+    /**
+     * Sample period in nanoseconds
+     */
+    pub fn get_period_ns(&self) -> i64 {
+        (NANOSECONDS_PER_SECOND/float::to_f64(self.rate)).round() as i64
+    }
+}
+
+/**
+ * One IMU measurement with its ground truth. Measurements are in the IMU frame,
+ * the pose (IMU to world) and velocity (world frame) are those at the true time of the sample.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct ImuSample<F: Float> {
+    timestamp_ns: i64,
+    angular_velocity: Vector3<F>,
+    linear_acceleration: Vector3<F>,
+    gyroscope_bias: Vector3<F>,
+    accelerometer_bias: Vector3<F>,
+    pose: Isometry3<F>,
+    velocity: Vector3<F>
+}
+
+impl<F: Float> ImuSample<F> {
+    pub fn new(timestamp_ns: i64, angular_velocity: Vector3<F>, linear_acceleration: Vector3<F>, gyroscope_bias: Vector3<F>, accelerometer_bias: Vector3<F>, pose: Isometry3<F>, velocity: Vector3<F>) -> ImuSample<F> {
+        ImuSample {timestamp_ns, angular_velocity, linear_acceleration, gyroscope_bias, accelerometer_bias, pose, velocity}
+    }
+
+    pub fn get_timestamp_ns(&self) -> i64 {self.timestamp_ns}
+    pub fn get_angular_velocity(&self) -> &Vector3<F> {&self.angular_velocity}
+    pub fn get_linear_acceleration(&self) -> &Vector3<F> {&self.linear_acceleration}
+    pub fn get_gyroscope_bias(&self) -> &Vector3<F> {&self.gyroscope_bias}
+    pub fn get_accelerometer_bias(&self) -> &Vector3<F> {&self.accelerometer_bias}
+    pub fn get_pose(&self) -> &Isometry3<F> {&self.pose}
+    pub fn get_velocity(&self) -> &Vector3<F> {&self.velocity}
+
+    pub fn get_timestamp(&self) -> F {
+        float::from_f64(self.timestamp_ns as f64/NANOSECONDS_PER_SECOND)
+    }
+}
+
+/**
+ * Samples gyroscope and accelerometer readings of an IMU moving with the trajectory. IMU timestamps from start_time to end_time (seconds)
+ * are spaced by the sample period. Measurements are the true angular velocity and specific force (acceleration minus gravity)
+ * plus bias and white noise. Biases follow a random walk. Pass a seeded rng for reproducible noise.
+ */
+pub fn generate_imu_samples<F: Float, T: KinematicTrajectory<F>, R: Rng>(trajectory: &T, start_time: F, end_time: F, parameters: &ImuParameters<F>, rng: &mut R) -> Vec<ImuSample<F>> {
+    let period_ns = parameters.get_period_ns();
+    let dt = period_ns as f64/NANOSECONDS_PER_SECOND;
+    let start_ns = (float::to_f64(start_time)*NANOSECONDS_PER_SECOND).round() as i64;
+    let end_ns = (float::to_f64(end_time)*NANOSECONDS_PER_SECOND).round() as i64;
+    let noise = parameters.get_noise();
+    let mut gaussian = |sigma: F| Vector3::from_fn(|_,_| float::from_f64::<F>(rng.sample::<f64,_>(StandardNormal))*sigma);
+    // Discrete standard deviations of the white noise and the bias increments
+    let sqrt_dt = float::from_f64::<F>(dt.sqrt());
+    let (gyroscope_sigma, accelerometer_sigma) = (noise.gyroscope_noise_density/sqrt_dt, noise.accelerometer_noise_density/sqrt_dt);
+    let (gyroscope_walk_sigma, accelerometer_walk_sigma) = (noise.gyroscope_random_walk*sqrt_dt, noise.accelerometer_random_walk*sqrt_dt);
+
+    let imu_to_body = parameters.get_imu_to_body();
+    let lever_arm = imu_to_body.translation.vector;
+    let mut gyroscope_bias = parameters.initial_gyroscope_bias;
+    let mut accelerometer_bias = parameters.initial_accelerometer_bias;
+    let mut samples = Vec::new();
+    let mut timestamp_ns = start_ns;
+    while timestamp_ns <= end_ns {
+        let time = float::from_f64::<F>(timestamp_ns as f64/NANOSECONDS_PER_SECOND) - parameters.get_time_offset();
+        let state = trajectory.state_at(time);
+        let body_rotation = state.pose.rotation;
+        let omega = state.angular_velocity;
+        // Rigid body motion of the point the IMU is mounted at
+        let velocity = state.linear_velocity + body_rotation*omega.cross(&lever_arm);
+        let acceleration = state.linear_acceleration + body_rotation*(state.angular_acceleration.cross(&lever_arm) + omega.cross(&omega.cross(&lever_arm)));
+        let pose = state.pose*imu_to_body;
+
+        let angular_velocity = imu_to_body.rotation.inverse_transform_vector(&omega) + gyroscope_bias + gaussian(gyroscope_sigma);
+        let specific_force = pose.rotation.inverse_transform_vector(&(acceleration - parameters.get_gravity()));
+        let linear_acceleration = specific_force + accelerometer_bias + gaussian(accelerometer_sigma);
+        samples.push(ImuSample::new(timestamp_ns, angular_velocity, linear_acceleration, gyroscope_bias, accelerometer_bias, pose, velocity));
+
+        gyroscope_bias += gaussian(gyroscope_walk_sigma);
+        accelerometer_bias += gaussian(accelerometer_walk_sigma);
+        timestamp_ns += period_ns;
+    }
+    samples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use na::{Translation3,UnitQuaternion};
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::spline::BSplineTrajectory;
+
+    fn spline() -> BSplineTrajectory<f64> {
+        let control_poses = (0..6).map(|i| {
+            let i = i as f64;
+            Isometry3::from_parts(Translation3::new(0.5*i, (0.9*i).sin(), 0.2*i*i), UnitQuaternion::from_euler_angles(0.3*i, (0.4*i).cos(), -0.25*i))
+        }).collect();
+        BSplineTrajectory::new(0.0, 0.5, control_poses)
+    }
+
+    fn parameters() -> ImuParameters<f64> {
+        ImuParameters::new(1000.0)
+            .imu_to_body(Isometry3::from_parts(Translation3::new(0.05, -0.1, 0.02), UnitQuaternion::from_euler_angles(0.1, 0.2, -0.3)))
+            .initial_biases(Vector3::new(0.01, -0.02, 0.005), Vector3::new(-0.1, 0.05, 0.2))
+    }
+
+    #[test]
+    fn noiseless_measurements_match_ground_truth() {
+        let parameters = parameters();
+        let samples = generate_imu_samples(&spline(), 0.1, 1.4, &parameters, &mut StdRng::seed_from_u64(0));
+        assert_eq!(samples.len(), 1301);
+        let dt = parameters.get_period_ns() as f64/NANOSECONDS_PER_SECOND;
+        for window in samples.windows(3) {
+            let [before, sample, after] = window else {unreachable!()};
+            // The acceleration is only continuous at the knots, differences across them are not accurate
+            if sample.get_timestamp_ns() % 500_000_000 == 0 {
+                continue;
+            }
+            let pose = sample.get_pose();
+            let velocity = (after.get_pose().translation.vector - before.get_pose().translation.vector)/(2.0*dt);
+            let acceleration = (after.get_velocity() - before.get_velocity())/(2.0*dt);
+            let angular_velocity = (before.get_pose().rotation.inverse()*after.get_pose().rotation).scaled_axis()/(2.0*dt);
+            let specific_force = sample.get_linear_acceleration() - sample.get_accelerometer_bias();
+            assert!((sample.get_velocity() - velocity).norm() < 1e-5);
+            assert!((pose.rotation*specific_force + parameters.get_gravity() - acceleration).norm() < 1e-4);
+            assert!((sample.get_angular_velocity() - sample.get_gyroscope_bias() - angular_velocity).norm() < 1e-5);
+            assert_eq!(sample.get_gyroscope_bias(), &Vector3::new(0.01, -0.02, 0.005));
+        }
+    }
+
+    #[test]
+    fn seeded_noise_is_reproducible() {
+        let noise = ImuNoise {gyroscope_noise_density: 1.7e-4, gyroscope_random_walk: 1.9e-5, accelerometer_noise_density: 2.0e-3, accelerometer_random_walk: 3.0e-3};
+        let noisy = parameters().noise(noise);
+        let samples = generate_imu_samples(&spline(), 0.0, 0.2, &noisy, &mut StdRng::seed_from_u64(7));
+        assert_eq!(samples, generate_imu_samples(&spline(), 0.0, 0.2, &noisy, &mut StdRng::seed_from_u64(7)));
+        assert_ne!(samples, generate_imu_samples(&spline(), 0.0, 0.2, &parameters(), &mut StdRng::seed_from_u64(7)));
+    }
+}
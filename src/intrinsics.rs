@@ -0,0 +1,207 @@
+extern crate nalgebra as na;
+
+use std::fmt;
+use na::Vector2;
+use crate::camera::{CameraDescription,Resolution};
+use crate::camera_model::Pinhole;
+use crate::convention::Convention;
+use crate::float::{self,Float};
+
+/**
+ * Ways of specifying the focal length of a pinhole camera. Angles are in radians, lengths in millimetres.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum FocalLength<F: Float> {
+    /// Focal length in pixels
+    Pixels {fx: F, fy: F},
+    /// Horizontal field of view, square pixels
+    HorizontalFov(F),
+    /// Vertical field of view, square pixels
+    VerticalFov(F),
+    /// Horizontal and vertical field of view
+    Fov {horizontal: F, vertical: F},
+    /// Physical sensor size and lens focal length
+    Sensor {sensor_width_mm: F, sensor_height_mm: F, focal_length_mm: F},
+    /// glTF perspective camera. Without an aspect ratio the aspect ratio of the resolution is used
+    GltfPerspective {yfov: F, aspect_ratio: Option<F>}
+}
+
+#[derive(Debug,Clone,Copy,PartialEq,Eq)]
+pub enum IntrinsicsError {
+    EmptyResolution,
+    /// Field of view has to be in (0, PI)
+    InvalidFov,
+    /// Lengths, focal lengths and aspect ratios have to be positive and finite
+    NonPositive,
+    NonFinite
+}
+
+impl fmt::Display for IntrinsicsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntrinsicsError::EmptyResolution => write!(f, "resolution has to be at least one pixel wide and high"),
+            IntrinsicsError::InvalidFov => write!(f, "field of view has to be in (0, PI)"),
+            IntrinsicsError::NonPositive => write!(f, "lengths and focal lengths have to be positive"),
+            IntrinsicsError::NonFinite => write!(f, "intrinsics have to be finite")
+        }
+    }
+}
+
+impl std::error::Error for IntrinsicsError {}
+
+/**
+ * Builds a pinhole camera with its resolution attached.
+ * The principal point defaults to the image center and the convention to OpenGL.
+ * For OpenGL the focal lengths of the resulting intrinsic matrix are negative, as the camera looks along -Z.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct IntrinsicsBuilder<F: Float> {
+    resolution: Resolution,
+    focal_length: FocalLength<F>,
+    convention: Convention,
+    principal_point: Option<Vector2<F>>,
+    skew: F
+}
+
+impl<F: Float> IntrinsicsBuilder<F> {
+    pub fn new(resolution: Resolution, focal_length: FocalLength<F>) -> IntrinsicsBuilder<F> {
+        IntrinsicsBuilder {resolution, focal_length, convention: Convention::OpenGl, principal_point: None, skew: F::zero()}
+    }
+
+    pub fn convention(self, convention: Convention) -> IntrinsicsBuilder<F> {
+        IntrinsicsBuilder {convention, ..self}
+    }
+
+    pub fn principal_point(self, cx: F, cy: F) -> IntrinsicsBuilder<F> {
+        IntrinsicsBuilder {principal_point: Some(Vector2::new(cx,cy)), ..self}
+    }
+
+    pub fn skew(self, skew: F) -> IntrinsicsBuilder<F> {
+        IntrinsicsBuilder {skew, ..self}
+    }
+
+    /**
+     * Focal lengths in pixels, always positive
+     */
+    fn focal_lengths(&self) -> Result<(F,F), IntrinsicsError> {
+        let two = float::from_f64::<F>(2.0);
+        let width = float::from_usize::<F>(self.resolution.get_width());
+        let height = float::from_usize::<F>(self.resolution.get_height());
+        let from_fov = |extent: F, fov: F| match fov > F::zero() && fov < F::pi() {
+            true => Ok(extent/two/(fov/two).tan()),
+            false => Err(IntrinsicsError::InvalidFov)
+        };
+        let positive = |v: F| match v > F::zero() {
+            true => Ok(v),
+            false => Err(IntrinsicsError::NonPositive)
+        };
+        match self.focal_length {
+            FocalLength::Pixels {fx, fy} => Ok((positive(fx.abs())?, positive(fy.abs())?)),
+            FocalLength::HorizontalFov(fov) => from_fov(width, fov).map(|f| (f,f)),
+            FocalLength::VerticalFov(fov) => from_fov(height, fov).map(|f| (f,f)),
+            FocalLength::Fov {horizontal, vertical} => Ok((from_fov(width, horizontal)?, from_fov(height, vertical)?)),
+            FocalLength::Sensor {sensor_width_mm, sensor_height_mm, focal_length_mm} => {
+                let focal_length_mm = positive(focal_length_mm)?;
+                Ok((focal_length_mm*width/positive(sensor_width_mm)?, focal_length_mm*height/positive(sensor_height_mm)?))
+            },
+            FocalLength::GltfPerspective {yfov, aspect_ratio} => {
+                let fy = from_fov(height, yfov)?;
+                // glTF scales x by 1/aspect_ratio in normalized device coordinates
+                let aspect_ratio = positive(aspect_ratio.unwrap_or(width/height))?;
+                Ok((fy*width/(height*aspect_ratio), fy))
+            }
+        }
+    }
+
+    pub fn build(&self) -> Result<CameraDescription<F,Pinhole<F>>, IntrinsicsError> {
+        if self.resolution.get_width() == 0 || self.resolution.get_height() == 0 {
+            return Err(IntrinsicsError::EmptyResolution);
+        }
+        let (fx, fy) = self.focal_lengths()?;
+        let two = float::from_f64::<F>(2.0);
+        let principal_point = self.principal_point.unwrap_or(Vector2::new(
+            float::from_usize::<F>(self.resolution.get_width())/two,
+            float::from_usize::<F>(self.resolution.get_height())/two));
+        let values = [fx, fy, principal_point.x, principal_point.y, self.skew];
+        if values.iter().any(|v| !v.is_finite()) {
+            return Err(IntrinsicsError::NonFinite);
+        }
+        let sign = self.convention.forward_sign::<F>();
+        let pinhole = Pinhole::from_focal_and_principal_point(fx*sign, fy*sign, principal_point.x, principal_point.y, self.skew);
+        Ok(CameraDescription::new(pinhole, self.resolution))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f64::consts::PI;
+
+    fn build(resolution: Resolution, focal_length: FocalLength<f64>) -> Result<(f64,f64,f64,f64), IntrinsicsError> {
+        IntrinsicsBuilder::new(resolution, focal_length).build().map(|description| {
+            let k = description.get_camera_model().get_intrinsic_matrix();
+            (k[(0,0)], k[(1,1)], k[(0,2)], k[(1,2)])
+        })
+    }
+
+    fn assert_focal_lengths(resolution: Resolution, focal_length: FocalLength<f64>, fx: f64, fy: f64) {
+        let (actual_fx, actual_fy, _, _) = build(resolution, focal_length).unwrap();
+        assert!((actual_fx.abs() - fx).abs() < 1e-9 && (actual_fy.abs() - fy).abs() < 1e-9, "{:?}: ({}, {}) instead of ({}, {})", focal_length, actual_fx, actual_fy, fx, fy);
+    }
+
+    #[test]
+    fn field_of_view() {
+        let resolution = Resolution::new(640, 480);
+        let f_60 = 240.0/(PI/6.0).tan();
+        assert_focal_lengths(resolution, FocalLength::HorizontalFov(PI/2.0), 320.0, 320.0);
+        assert_focal_lengths(resolution, FocalLength::VerticalFov(PI/3.0), f_60, f_60);
+        assert_focal_lengths(resolution, FocalLength::Fov {horizontal: PI/2.0, vertical: PI/3.0}, 320.0, f_60);
+    }
+
+    #[test]
+    fn sensor_size() {
+        // 36x24 mm full frame sensor with a 50 mm lens
+        let focal_length = FocalLength::Sensor {sensor_width_mm: 36.0, sensor_height_mm: 24.0, focal_length_mm: 50.0};
+        assert_focal_lengths(Resolution::new(6000, 4000), focal_length, 50.0*6000.0/36.0, 50.0*4000.0/24.0);
+    }
+
+    #[test]
+    fn gltf_perspective() {
+        let resolution = Resolution::new(640, 480);
+        let fy = 240.0/(PI/6.0).tan();
+        assert_focal_lengths(resolution, FocalLength::GltfPerspective {yfov: PI/3.0, aspect_ratio: None}, fy, fy);
+        // A wider aspect ratio than the resolution squeezes x
+        assert_focal_lengths(resolution, FocalLength::GltfPerspective {yfov: PI/3.0, aspect_ratio: Some(2.0)}, fy*640.0/960.0, fy);
+    }
+
+    #[test]
+    fn convention_and_principal_point() {
+        let resolution = Resolution::new(640, 480);
+        assert_eq!(build(resolution, FocalLength::Pixels {fx: 500.0, fy: 400.0}), Ok((-500.0, -400.0, 320.0, 240.0)));
+        let description = IntrinsicsBuilder::new(resolution, FocalLength::Pixels {fx: 500.0, fy: 400.0})
+            .convention(Convention::OpenCv)
+            .principal_point(300.0, 250.0)
+            .skew(1.5)
+            .build().unwrap();
+        let k = description.get_camera_model().get_intrinsic_matrix();
+        assert_eq!((k[(0,0)], k[(1,1)], k[(0,2)], k[(1,2)], k[(0,1)]), (500.0, 400.0, 300.0, 250.0, 1.5));
+        assert_eq!(description.get_resolution(), resolution);
+    }
+
+    #[test]
+    fn errors() {
+        let resolution = Resolution::new(640, 480);
+        for fov in [0.0, PI, -0.5, 4.0, f64::NAN] {
+            assert_eq!(build(resolution, FocalLength::HorizontalFov(fov)), Err(IntrinsicsError::InvalidFov));
+            assert_eq!(build(resolution, FocalLength::GltfPerspective {yfov: fov, aspect_ratio: None}), Err(IntrinsicsError::InvalidFov));
+        }
+        assert_eq!(build(resolution, FocalLength::Pixels {fx: 0.0, fy: 400.0}), Err(IntrinsicsError::NonPositive));
+        assert_eq!(build(resolution, FocalLength::Sensor {sensor_width_mm: 36.0, sensor_height_mm: 24.0, focal_length_mm: -50.0}), Err(IntrinsicsError::NonPositive));
+        assert_eq!(build(resolution, FocalLength::Sensor {sensor_width_mm: 0.0, sensor_height_mm: 24.0, focal_length_mm: 50.0}), Err(IntrinsicsError::NonPositive));
+        assert_eq!(build(resolution, FocalLength::GltfPerspective {yfov: 1.0, aspect_ratio: Some(-1.0)}), Err(IntrinsicsError::NonPositive));
+        assert_eq!(build(resolution, FocalLength::Pixels {fx: f64::INFINITY, fy: 400.0}), Err(IntrinsicsError::NonFinite));
+        for empty in [Resolution::new(0, 480), Resolution::new(640, 0)] {
+            assert_eq!(build(empty, FocalLength::HorizontalFov(1.0)), Err(IntrinsicsError::EmptyResolution));
+        }
+    }
+}
@@ -0,0 +1,99 @@
+extern crate nalgebra as na;
+
+use std::fs::{self,File};
+use std::io::{BufWriter,Write,Error,ErrorKind};
+use na::{Vector3,Quaternion,UnitQuaternion,Isometry3,Translation3};
+use crate::float::Float;
+use crate::imu::ImuSample;
+
+const IMU_HEADER: &str = "#timestamp [ns],w_RS_S_x [rad s^-1],w_RS_S_y [rad s^-1],w_RS_S_z [rad s^-1],a_RS_S_x [m s^-2],a_RS_S_y [m s^-2],a_RS_S_z [m s^-2]";
+const GROUNDTRUTH_HEADER: &str = "#timestamp,p_RS_R_x [m],p_RS_R_y [m],p_RS_R_z [m],q_RS_w [],q_RS_x [],q_RS_y [],q_RS_z [],v_RS_R_x [m s^-1],v_RS_R_y [m s^-1],v_RS_R_z [m s^-1],b_w_RS_S_x [rad s^-1],b_w_RS_S_y [rad s^-1],b_w_RS_S_z [rad s^-1],b_a_RS_S_x [m s^-2],b_a_RS_S_y [m s^-2],b_a_RS_S_z [m s^-2]";
+const IMU_COLUMNS: usize = 7;
+const GROUNDTRUTH_COLUMNS: usize = 17;
+
+/**
+ * Writes the measurements in the layout of EuRoC's imu0/data.csv and the ground truth in the layout of state_groundtruth_estimate0/data.csv
+ */
+pub fn write_euroc_imu<F: Float>(data_path_str: &str, groundtruth_path_str: &str, samples: &[ImuSample<F>]) -> std::io::Result<()> {
+    let mut data = BufWriter::new(File::create(data_path_str)?);
+    let mut groundtruth = BufWriter::new(File::create(groundtruth_path_str)?);
+    writeln!(data, "{}", IMU_HEADER)?;
+    writeln!(groundtruth, "{}", GROUNDTRUTH_HEADER)?;
+    for sample in samples {
+        let (w, a) = (sample.get_angular_velocity(), sample.get_linear_acceleration());
+        writeln!(data, "{},{},{},{},{},{},{}", sample.get_timestamp_ns(), w.x, w.y, w.z, a.x, a.y, a.z)?;
+        let (p, q, v) = (sample.get_pose().translation.vector, sample.get_pose().rotation, sample.get_velocity());
+        let (b_w, b_a) = (sample.get_gyroscope_bias(), sample.get_accelerometer_bias());
+        writeln!(groundtruth, "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}", sample.get_timestamp_ns(),
+            p.x, p.y, p.z, q.w, q.i, q.j, q.k, v.x, v.y, v.z, b_w.x, b_w.y, b_w.z, b_a.x, b_a.y, b_a.z)?;
+    }
+    data.flush()?;
+    groundtruth.flush()
+}
+
+/**
+ * Reads files written by write_euroc_imu. Rows of both files have to share their timestamps.
+ */
+pub fn read_euroc_imu<F: Float>(data_path_str: &str, groundtruth_path_str: &str) -> std::io::Result<Vec<ImuSample<F>>> {
+    let data = read_rows::<F>(&fs::read_to_string(data_path_str)?, IMU_COLUMNS)?;
+    let groundtruth = read_rows::<F>(&fs::read_to_string(groundtruth_path_str)?, GROUNDTRUTH_COLUMNS)?;
+    if data.len() != groundtruth.len() {
+        return Err(Error::new(ErrorKind::InvalidData, "IMU data and ground truth differ in length"));
+    }
+    data.into_iter().zip(groundtruth).map(|((timestamp_ns,d),(gt_timestamp_ns,g))| {
+        if timestamp_ns != gt_timestamp_ns {
+            return Err(Error::new(ErrorKind::InvalidData, format!("IMU ground truth missing for timestamp {}", timestamp_ns)));
+        }
+        let rotation = UnitQuaternion::new_unchecked(Quaternion::new(g[3], g[4], g[5], g[6]));
+        let pose = Isometry3::from_parts(Translation3::new(g[0], g[1], g[2]), rotation);
+        Ok(ImuSample::new(timestamp_ns,
+            Vector3::new(d[0], d[1], d[2]), Vector3::new(d[3], d[4], d[5]),
+            Vector3::new(g[10], g[11], g[12]), Vector3::new(g[13], g[14], g[15]),
+            pose, Vector3::new(g[7], g[8], g[9])))
+    }).collect()
+}
+
+/**
+ * Timestamp and the remaining values of every row, comment lines are skipped
+ */
+fn read_rows<F: Float>(content: &str, columns: usize) -> std::io::Result<Vec<(i64,Vec<F>)>> {
+    let invalid = |line: &str| Error::new(ErrorKind::InvalidData, format!("Invalid EuRoC row: {}", line));
+    content.lines().filter(|line| !line.starts_with('#') && !line.trim().is_empty()).map(|line| {
+        let fields = line.split(',').map(str::trim).collect::<Vec<_>>();
+        if fields.len() != columns {
+            return Err(invalid(line));
+        }
+        let timestamp_ns = fields[0].parse::<i64>().map_err(|_| invalid(line))?;
+        let values = fields[1..].iter().map(|v| v.parse::<F>().map_err(|_| invalid(line))).collect::<Result<Vec<_>,_>>()?;
+        Ok((timestamp_ns, values))
+    }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_util::TempPath;
+
+    #[test]
+    fn round_trip() {
+        let samples = (0..10).map(|i| {
+            let t = i as f64*0.1;
+            let pose = Isometry3::from_parts(Translation3::new(t, -2.0*t, 0.1), UnitQuaternion::from_euler_angles(t, 0.3, -t/3.0));
+            ImuSample::new(1_000_000*i + 1_403_636_579_763_555_584, Vector3::new(0.1, t, -1.0/3.0), Vector3::new(9.81, t/7.0, 0.0),
+                Vector3::new(1e-4, 0.0, -2e-5), Vector3::new(0.01, 0.02, 1.0/3.0), pose, Vector3::new(t.sin(), 0.0, 1.0))
+        }).collect::<Vec<_>>();
+        let (data_path, groundtruth_path) = (TempPath::new("data.csv"), TempPath::new("groundtruth.csv"));
+        write_euroc_imu(&data_path, &groundtruth_path, &samples).unwrap();
+        let read = read_euroc_imu::<f64>(&data_path, &groundtruth_path);
+        assert_eq!(read.unwrap(), samples);
+    }
+
+    #[test]
+    fn mismatched_timestamps_are_rejected() {
+        let (data_path, groundtruth_path) = (TempPath::new("mismatch_data.csv"), TempPath::new("mismatch_groundtruth.csv"));
+        fs::write(&data_path, format!("{}\n1,0,0,0,0,0,0\n", IMU_HEADER)).unwrap();
+        fs::write(&groundtruth_path, format!("{}\n2,0,0,0,1,0,0,0,0,0,0,0,0,0,0,0,0\n", GROUNDTRUTH_HEADER)).unwrap();
+        let error = read_euroc_imu::<f64>(&data_path, &groundtruth_path).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,346 @@
+extern crate nalgebra as na;
+mod euroc;
+mod stream;
+mod tum;
+
+use crate::camera_features::CameraFeatures;
+use crate::landmark::Landmark;
+use crate::float::Float;
+use crate::schema::{CameraFeaturesFile,CameraFeaturesDocument,CameraFeaturesBinaryDocument,LandmarksFile,LandmarksDocument,SCHEMA_VERSION};
+use std::fs::File;
+use std::io::{BufReader,BufWriter,Read,Write,Error,ErrorKind};
+use std::path::Path;
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use na::Vector2;
+
+pub use euroc::{write_euroc_imu,read_euroc_imu};
+pub use stream::CameraFeaturesWriter;
+pub use tum::{write_tum_trajectory,write_tum_camera_trajectory,read_tum_trajectory};
+
+/**
+ * Backends for dataset files.
+ * Binary is a compact little-endian encoding where every array is prefixed by its u64 length.
+ * Binary files can only be read back with the scalar type and schema version they were written with.
+ */
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Format {
+    Yaml,
+    Json,
+    Binary
+}
+
+impl Format {
+    /**
+     * Infers the format from the file extension. Unknown extensions fall back to YAML.
+     */
+    pub fn from_path(path_str: &str) -> Format {
+        match Path::new(path_str).extension().and_then(|e| e.to_str()) {
+            Some("json") => Format::Json,
+            Some("bin") => Format::Binary,
+            _ => Format::Yaml
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Yaml => "yaml",
+            Format::Json => "json",
+            Format::Binary => "bin"
+        }
+    }
+}
+
+fn to_io_error<E: std::error::Error + Send + Sync + 'static>(error: E) -> Error {
+    Error::new(ErrorKind::InvalidData, error)
+}
+
+fn write_document<F: Float, T: Serialize>(path_str: &str, document: &T, format: Format) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path_str)?);
+    match format {
+        Format::Yaml => serde_yaml::to_writer(&mut writer, document).map_err(to_io_error)?,
+        Format::Json => serde_json::to_writer(&mut writer, document).map_err(to_io_error)?,
+        Format::Binary => {
+            writer.write_all(&F::BINARY_MAGIC)?;
+            bincode::serialize_into(&mut writer, document).map_err(to_io_error)?
+        }
+    };
+    writer.flush()
+}
+
+/**
+ * Text formats are read through the file type T, which also accepts older layouts.
+ * The binary format is read through its fixed layout B. Bincode decodes fields by position and every schema version
+ * changed that layout, so only binary files of the current version can be read.
+ */
+fn read_document<F: Float, T: DeserializeOwned + TryInto<D,Error=Error>, B: DeserializeOwned + Into<D>, D>(path_str: &str, format: Format) -> std::io::Result<D> {
+    let mut reader = BufReader::new(File::open(path_str)?);
+    match format {
+        Format::Yaml => serde_yaml::from_reader::<_,T>(reader).map_err(to_io_error)?.try_into(),
+        Format::Json => serde_json::from_reader::<_,T>(reader).map_err(to_io_error)?.try_into(),
+        Format::Binary => {
+            let mut magic = [0u8;4];
+            reader.read_exact(&mut magic)?;
+            if magic != F::BINARY_MAGIC {
+                return Err(Error::new(ErrorKind::InvalidData, "Not a models-cv binary file of the requested precision"));
+            }
+            // The version is the leading field of every binary document
+            let mut version_bytes = [0u8;4];
+            reader.read_exact(&mut version_bytes)?;
+            let version = u32::from_le_bytes(version_bytes);
+            if version != SCHEMA_VERSION {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Binary file has schema version {}, only version {} can be read", version, SCHEMA_VERSION)));
+            }
+            bincode::deserialize_from::<_,B>((&version_bytes[..]).chain(reader)).map(|b| b.into()).map_err(to_io_error)
+        }
+    }
+}
+
+pub fn serialize_feature_matches_with_format<F: Float>(path_str: &str, camera_feature_vec: &[CameraFeatures<F>], format: Format) -> std::io::Result<()> {
+    let document = CameraFeatures::to_serial(camera_feature_vec);
+    match format {
+        Format::Binary => write_document::<F,_>(path_str, &CameraFeaturesBinaryDocument::from(document), format),
+        _ => write_document::<F,_>(path_str, &document, format)
+    }
+}
+
+pub fn deserialize_feature_matches_with_format<F: Float>(path_str: &str, format: Format) -> std::io::Result<Vec<CameraFeatures<F>>> {
+    let document = read_document::<F,CameraFeaturesFile<F>,CameraFeaturesBinaryDocument<F>,CameraFeaturesDocument<F>>(path_str, format)?;
+    CameraFeatures::try_from_serial(&document)
+}
+
+pub fn serialize_landmarks_with_format<F: Float>(path_str: &str, landmark_vec: &[Landmark<F>], format: Format) -> std::io::Result<()> {
+    write_document::<F,_>(path_str, &Landmark::to_serial(landmark_vec), format)
+}
+
+pub fn deserialize_landmarks_with_format<F: Float>(path_str: &str, format: Format) -> std::io::Result<Vec<Landmark<F>>> {
+    let document = read_document::<F,LandmarksFile<F>,LandmarksDocument<F>,LandmarksDocument<F>>(path_str, format)?;
+    Ok(Landmark::from_serial(&document))
+}
+
+/**
+ * The format is inferred from the file extension
+ */
+pub fn serialize_feature_matches<F: Float>(path_str: &str, camera_feature_vec: &[CameraFeatures<F>]) -> std::io::Result<()> {
+    serialize_feature_matches_with_format(path_str, camera_feature_vec, Format::from_path(path_str))
+}
+
+/**
+ * The format is inferred from the file extension. Reads both versioned documents and the unversioned tuple format
+ */
+pub fn deserialize_feature_matches<F: Float>(path_str: &str) -> Vec<CameraFeatures<F>> {
+    deserialize_feature_matches_with_format(path_str, Format::from_path(path_str)).expect("Unable to load camera features")
+}
+
+/**
+ * The format is inferred from the file extension
+ */
+pub fn serialize_landmarks<F: Float>(path_str: &str, landmark_vec: &[Landmark<F>]) -> std::io::Result<()> {
+    serialize_landmarks_with_format(path_str, landmark_vec, Format::from_path(path_str))
+}
+
+/**
+ * The format is inferred from the file extension. Reads both versioned documents and the unversioned tuple format
+ */
+pub fn deserialize_landmarks<F: Float>(path_str: &str) -> Vec<Landmark<F>> {
+    deserialize_landmarks_with_format(path_str, Format::from_path(path_str)).expect("Unable to load landmarks")
+}
+
+pub fn calculate_rgb_byte_vec(screen_points: &[Vector2<usize>], screen_width: usize, screen_height: usize) -> Vec<u8> {
+    let mut dat_vec: Vec<u8> = vec![0;3*screen_width*screen_height];
+
+    let screen_points_in_range = screen_points.iter().filter(|p| p.x < screen_width && p.y < screen_height);
+
+    for screen_point in screen_points_in_range {
+        let x = screen_point.x;
+        let y = screen_point.y;
+        let screen_idx = y*screen_width + x;
+        let byte_idx = 3*screen_idx;
+        dat_vec[byte_idx] = 255;
+        dat_vec[byte_idx+1] = 255;
+        dat_vec[byte_idx+2] = 255;
+    }
+
+    dat_vec
+}
+
+pub fn write_png_data_to_file(path_str: &str, data_vec: &[u8], screen_width: u32, screen_height: u32) -> std::io::Result<()> {
+    let writer = BufWriter::new(File::create(path_str)?);
+
+    let mut encoder = png::Encoder::new(writer, screen_width, screen_height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_source_gamma(png::ScaledFloat::new(1.0 / 2.2));     // 1.0 / 2.2, unscaled, but rounded
+    let source_chromaticities = png::SourceChromaticities::new(     // Using unscaled instantiation here
+        (0.31270, 0.32900),
+        (0.64000, 0.33000),
+        (0.30000, 0.60000),
+        (0.15000, 0.06000)
+    );
+    encoder.set_source_chromaticities(source_chromaticities);
+    let mut png_writer = encoder.write_header().map_err(to_io_error)?;
+
+    png_writer.write_image_data(data_vec).map_err(to_io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use na::{Vector3,Matrix3,Matrix3x4};
+    use crate::camera::Resolution;
+    use crate::camera_model::pinhole::Pinhole;
+    use crate::feature::Feature;
+    use crate::rig::RigMembership;
+    use crate::schema::{LegacyCameraFeaturesSerial,LegacyLandmarkSerial};
+    use crate::test_util::TempPath;
+
+    fn camera_features() -> Vec<CameraFeatures<f64>> {
+        let model = Pinhole::from_focal_and_principal_point(500.0, 510.0, 320.0, 240.0, 0.0);
+        let view_matrix = Matrix3x4::new(
+            1.0, 0.0, 0.0, 0.1,
+            0.0, 1.0, 0.0, -0.2,
+            0.0, 0.0, 1.0, 0.3);
+        let feature_map = HashMap::from([
+            (0, Feature::new(&Vector2::new(10.25, 20.5)).with_bearing(Some(Vector3::new(0.0, 0.6, -0.8)))),
+            (3, Feature::new(&Vector2::new(100.125, 7.75)))
+        ]);
+        vec![
+            CameraFeatures::new(feature_map, 0, view_matrix, &model, Resolution::new(640, 480)).with_timestamp(Some(1.5)),
+            CameraFeatures::new(HashMap::new(), 1, view_matrix, &model, Resolution::new(640, 480))
+        ]
+    }
+
+    fn landmarks() -> Vec<Landmark<f64>> {
+        vec![Landmark::new(&0, &Vector3::new(1.0, 2.0, -3.0)), Landmark::new(&3, &Vector3::new(-0.5, 0.25, -10.0))]
+    }
+
+    #[test]
+    fn text_round_trip() {
+        for format in [Format::Yaml, Format::Json] {
+            let camera_features_path = TempPath::new(&format!("camera_features.{}", format.extension()));
+            let landmarks_path = TempPath::new(&format!("landmarks.{}", format.extension()));
+            serialize_feature_matches(&camera_features_path, &camera_features()).unwrap();
+            serialize_landmarks(&landmarks_path, &landmarks()).unwrap();
+
+            assert_eq!(deserialize_feature_matches::<f64>(&camera_features_path), camera_features());
+            assert_eq!(deserialize_landmarks::<f64>(&landmarks_path), landmarks());
+        }
+    }
+
+    #[test]
+    fn text_omits_missing_optionals() {
+        let path = TempPath::new("omitted.yaml");
+        serialize_feature_matches(&path, &camera_features()).unwrap();
+        let yaml = std::fs::read_to_string(&path).unwrap();
+        assert!(!yaml.contains("null"));
+        assert!(!yaml.contains("~"));
+    }
+
+    #[test]
+    fn legacy_tuple_files() {
+        let view_matrix = [1.0, 0.0, 0.0, 0.5, 0.0, 1.0, 0.0, 0.0, 0.0, 0.0, 1.0, -1.0];
+        let intrinsic_matrix = [400.0, 0.0, 320.0, 0.0, 400.0, 240.0, 0.0, 0.0, 1.0];
+        let legacy_cameras: Vec<LegacyCameraFeaturesSerial> = vec![(2, view_matrix, intrinsic_matrix, vec![(7,(11,13))])];
+        let legacy_landmarks: Vec<LegacyLandmarkSerial> = vec![(7, [1.0, -2.0, 3.0])];
+        let camera_features_path = TempPath::new("legacy_camera_features.json");
+        let landmarks_path = TempPath::new("legacy_landmarks.yaml");
+        std::fs::write(&camera_features_path, serde_json::to_string(&legacy_cameras).unwrap()).unwrap();
+        std::fs::write(&landmarks_path, serde_yaml::to_string(&legacy_landmarks).unwrap()).unwrap();
+
+        let camera_features = deserialize_feature_matches::<f64>(&camera_features_path);
+        let landmarks = deserialize_landmarks::<f64>(&landmarks_path);
+
+        assert_eq!(camera_features.len(), 1);
+        let cf = &camera_features[0];
+        assert_eq!(cf.get_cam_id(), 2);
+        assert_eq!(cf.get_resolution(), None);
+        assert_eq!(cf.get_view_matrix(), Matrix3x4::from_row_slice(&view_matrix.map(|v| v as f64)));
+        let model = cf.get_camera_model::<Pinhole<f64>>().unwrap();
+        assert_eq!(*model.get_intrinsic_matrix(), Matrix3::from_row_slice(&intrinsic_matrix.map(|v| v as f64)));
+        let feature = cf.get_feature_map()[&7];
+        assert_eq!(*feature.get_pixel(), Vector2::new(11, 13));
+        assert_eq!(*feature.get_position(), Vector2::new(11.0, 13.0));
+        assert_eq!(landmarks, vec![Landmark::new(&7, &Vector3::new(1.0, -2.0, 3.0))]);
+    }
+
+    #[test]
+    fn newer_text_version_is_rejected() {
+        for format in [Format::Yaml, Format::Json] {
+            let camera_features_path = TempPath::new(&format!("newer_camera_features.{}", format.extension()));
+            let landmarks_path = TempPath::new(&format!("newer_landmarks.{}", format.extension()));
+            let (camera_features, landmarks) = match format {
+                Format::Json => ("{\"version\": 99, \"cameras\": []}", "{\"version\": 99, \"landmarks\": []}"),
+                _ => ("version: 99\ncameras: []\n", "version: 99\nlandmarks: []\n")
+            };
+            std::fs::write(&camera_features_path, camera_features).unwrap();
+            std::fs::write(&landmarks_path, landmarks).unwrap();
+
+            let camera_features_error = deserialize_feature_matches_with_format::<f64>(&camera_features_path, format).unwrap_err();
+            let landmarks_error = deserialize_landmarks_with_format::<f64>(&landmarks_path, format).unwrap_err();
+            assert_eq!(camera_features_error.kind(), ErrorKind::InvalidData);
+            assert_eq!(landmarks_error.kind(), ErrorKind::InvalidData);
+        }
+    }
+
+    #[test]
+    fn binary_round_trip() {
+        let camera_features_path = TempPath::new("camera_features.bin");
+        let landmarks_path = TempPath::new("landmarks.bin");
+        serialize_feature_matches(&camera_features_path, &camera_features()).unwrap();
+        serialize_landmarks(&landmarks_path, &landmarks()).unwrap();
+
+        assert_eq!(deserialize_feature_matches::<f64>(&camera_features_path), camera_features());
+        assert_eq!(deserialize_landmarks::<f64>(&landmarks_path), landmarks());
+    }
+
+    #[test]
+    fn binary_precision_mismatch_is_rejected() {
+        let path = TempPath::new("precision_landmarks.bin");
+        serialize_landmarks(&path, &landmarks()).unwrap();
+        let error = deserialize_landmarks_with_format::<f32>(&path, Format::Binary).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn binary_version_mismatch_is_rejected() {
+        let camera_features_path = TempPath::new("version_camera_features.bin");
+        let landmarks_path = TempPath::new("version_landmarks.bin");
+        serialize_feature_matches(&camera_features_path, &camera_features()).unwrap();
+        serialize_landmarks(&landmarks_path, &landmarks()).unwrap();
+        // The version follows the four magic bytes
+        for (path, version) in [(&camera_features_path, SCHEMA_VERSION-1), (&landmarks_path, SCHEMA_VERSION+1)] {
+            let mut bytes = std::fs::read(path).unwrap();
+            bytes[4..8].copy_from_slice(&version.to_le_bytes());
+            std::fs::write(path, bytes).unwrap();
+        }
+
+        let camera_features_error = deserialize_feature_matches_with_format::<f64>(&camera_features_path, Format::Binary).unwrap_err();
+        let landmarks_error = deserialize_landmarks_with_format::<f64>(&landmarks_path, Format::Binary).unwrap_err();
+        assert_eq!(camera_features_error.kind(), ErrorKind::InvalidData);
+        assert_eq!(landmarks_error.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn non_rigid_transforms_are_rejected() {
+        let mut with_rig = camera_features();
+        with_rig[1] = with_rig[1].clone().with_rig(Some(RigMembership::new(0, 1, 0, na::Isometry3::translation(0.1, 0.0, 0.0))));
+        for format in [Format::Yaml, Format::Json] {
+            let mut scaled_view = CameraFeatures::to_serial(&with_rig);
+            scaled_view.cameras[0].view_matrix[0] = 2.0;
+            let mut scaled_rig = CameraFeatures::to_serial(&with_rig);
+            scaled_rig.cameras[1].rig.as_mut().unwrap().sensor_to_body[5] = 2.0;
+            for document in [scaled_view, scaled_rig] {
+                let path = TempPath::new(&format!("non_rigid.{}", format.extension()));
+                let text = match format {
+                    Format::Json => serde_json::to_string(&document).unwrap(),
+                    _ => serde_yaml::to_string(&document).unwrap()
+                };
+                std::fs::write(&path, text).unwrap();
+                let error = deserialize_feature_matches_with_format::<f64>(&path, format).unwrap_err();
+                assert_eq!(error.kind(), ErrorKind::InvalidData);
+            }
+        }
+    }
+}
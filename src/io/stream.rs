@@ -0,0 +1,128 @@
+use std::fs::File;
+use std::io::{BufWriter,Seek,SeekFrom,Write};
+use crate::camera_features::CameraFeatures;
+use crate::float::Float;
+use crate::schema::{CameraFeaturesBinarySerial,SCHEMA_VERSION};
+use super::{Format,to_io_error};
+
+/**
+ * Writes camera features one camera at a time. The finished file is identical to the one written by serialize_feature_matches_with_format,
+ * so it can be loaded with the regular deserialization functions.
+ */
+pub struct CameraFeaturesWriter<F: Float> {
+    writer: BufWriter<File>,
+    format: Format,
+    camera_count: usize,
+    _scalar: std::marker::PhantomData<F>
+}
+
+impl<F: Float> CameraFeaturesWriter<F> {
+    pub fn new(path_str: &str, format: Format) -> std::io::Result<CameraFeaturesWriter<F>> {
+        let mut writer = BufWriter::new(File::create(path_str)?);
+        match format {
+            Format::Yaml => writeln!(writer, "version: {}", SCHEMA_VERSION)?,
+            Format::Json => write!(writer, "{{\"version\":{},\"cameras\":[", SCHEMA_VERSION)?,
+            Format::Binary => {
+                writer.write_all(&F::BINARY_MAGIC)?;
+                writer.write_all(&SCHEMA_VERSION.to_le_bytes())?;
+                // Placeholder for the camera count. Patched in finish()
+                writer.write_all(&0u64.to_le_bytes())?;
+            }
+        };
+        Ok(CameraFeaturesWriter {writer, format, camera_count: 0, _scalar: std::marker::PhantomData})
+    }
+
+    pub fn get_camera_count(&self) -> usize {self.camera_count}
+
+    pub fn write(&mut self, camera_features: &CameraFeatures<F>) -> std::io::Result<()> {
+        let serial = camera_features.to_camera_serial();
+        match self.format {
+            Format::Yaml => {
+                if self.camera_count == 0 {
+                    self.writer.write_all(b"cameras:\n")?;
+                }
+                // A single element sequence is emitted as a block entry "- ..." which continues the cameras sequence
+                serde_yaml::to_writer(&mut self.writer, &[serial]).map_err(to_io_error)?
+            },
+            Format::Json => {
+                if self.camera_count > 0 {
+                    self.writer.write_all(b",")?;
+                }
+                serde_json::to_writer(&mut self.writer, &serial).map_err(to_io_error)?
+            },
+            Format::Binary => bincode::serialize_into(&mut self.writer, &CameraFeaturesBinarySerial::from(serial)).map_err(to_io_error)?
+        };
+        self.camera_count += 1;
+        Ok(())
+    }
+
+    /**
+     * Completes the document. Has to be called, otherwise the file is left truncated.
+     */
+    pub fn finish(mut self) -> std::io::Result<usize> {
+        match self.format {
+            Format::Yaml => {
+                if self.camera_count == 0 {
+                    self.writer.write_all(b"cameras: []\n")?;
+                }
+            },
+            Format::Json => self.writer.write_all(b"]}")?,
+            Format::Binary => {
+                self.writer.flush()?;
+                let file = self.writer.get_mut();
+                file.seek(SeekFrom::Start((F::BINARY_MAGIC.len() + std::mem::size_of::<u32>()) as u64))?;
+                file.write_all(&(self.camera_count as u64).to_le_bytes())?;
+            }
+        };
+        self.writer.flush()?;
+        Ok(self.camera_count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use na::{Vector2,Vector3,Matrix3x4,Isometry3,Translation3,UnitQuaternion};
+    use crate::camera::Resolution;
+    use crate::camera_model::KannalaBrandt;
+    use crate::feature::Feature;
+    use crate::io::{serialize_feature_matches_with_format,deserialize_feature_matches_with_format};
+    use crate::rig::RigMembership;
+    use crate::test_util::TempPath;
+
+    fn camera_features() -> Vec<CameraFeatures<f64>> {
+        let model = KannalaBrandt::new(-250.0, -250.0, 320.0, 240.0, [0.01, -0.005, 0.001, 0.0]);
+        (0..3).map(|cam_id| {
+            let view_matrix = Matrix3x4::new(
+                1.0, 0.0, 0.0, 0.1*cam_id as f64,
+                0.0, 1.0, 0.0, -0.2,
+                0.0, 0.0, 1.0, 1.0/3.0);
+            let feature_map = (0..4).map(|id| (id, Feature::new(&Vector2::new(10.25*id as f64, 20.5 + cam_id as f64))
+                .with_bearing(Some(Vector3::new(0.0, 0.6, -0.8)))
+                .with_timestamp(Some(0.001*id as f64)))).collect::<HashMap<_,_>>();
+            let rig = RigMembership::new(0, cam_id % 2, cam_id/2, Isometry3::from_parts(Translation3::new(0.1, 0.0, 0.0), UnitQuaternion::identity()));
+            CameraFeatures::new(feature_map, cam_id, view_matrix, &model, Resolution::new(640, 480))
+                .with_timestamp(Some(cam_id as f64/30.0))
+                .with_rig(Some(rig))
+        }).collect()
+    }
+
+    #[test]
+    fn matches_batch_serialization() {
+        for format in [Format::Yaml, Format::Json, Format::Binary] {
+            for camera_features in [camera_features(), vec![]] {
+                let (streamed, batch) = (TempPath::new(&format!("streamed.{}", format.extension())), TempPath::new(&format!("batch.{}", format.extension())));
+                let mut writer = CameraFeaturesWriter::<f64>::new(&streamed, format).unwrap();
+                for cf in &camera_features {
+                    writer.write(cf).unwrap();
+                }
+                assert_eq!(writer.finish().unwrap(), camera_features.len());
+                serialize_feature_matches_with_format(&batch, &camera_features, format).unwrap();
+
+                assert_eq!(std::fs::read(&streamed).unwrap(), std::fs::read(&batch).unwrap(), "{:?} with {} cameras", format, camera_features.len());
+                assert_eq!(deserialize_feature_matches_with_format::<f64>(&streamed, format).unwrap(), camera_features);
+            }
+        }
+    }
+}
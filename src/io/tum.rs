@@ -0,0 +1,95 @@
+extern crate nalgebra as na;
+
+use std::fs::{self,File};
+use std::io::{BufWriter,Write,Error,ErrorKind};
+use na::{Quaternion,UnitQuaternion,Isometry3,Translation3};
+use crate::camera_features::CameraFeatures;
+use crate::float::Float;
+
+const TUM_COLUMNS: usize = 8;
+
+/**
+ * Writes timestamped camera to world poses in the TUM RGB-D trajectory format: "timestamp tx ty tz qx qy qz qw" with the time in seconds.
+ * The format is read by common odometry evaluation tools.
+ */
+pub fn write_tum_trajectory<F: Float>(path_str: &str, timestamped_poses: &[(F,Isometry3<F>)]) -> std::io::Result<()> {
+    let mut writer = BufWriter::new(File::create(path_str)?);
+    writeln!(writer, "# timestamp tx ty tz qx qy qz qw")?;
+    for (time, pose) in timestamped_poses {
+        let (t, q) = (pose.translation.vector, pose.rotation);
+        writeln!(writer, "{} {} {} {} {} {} {} {}", time, t.x, t.y, t.z, q.i, q.j, q.k, q.w)?;
+    }
+    writer.flush()
+}
+
+/**
+ * Writes the poses of all cameras with a timestamp in the TUM format, ordered by time
+ */
+pub fn write_tum_camera_trajectory<F: Float>(path_str: &str, camera_features: &[CameraFeatures<F>]) -> std::io::Result<()> {
+    let mut timestamped_poses = camera_features.iter()
+        .filter_map(|c| c.get_timestamp().map(|t| (t, c.get_pose())))
+        .collect::<Vec<_>>();
+    timestamped_poses.sort_by(|(a,_),(b,_)| a.partial_cmp(b).expect("Timestamps have to be comparable"));
+    write_tum_trajectory(path_str, &timestamped_poses)
+}
+
+/**
+ * Reads a TUM trajectory. Lines starting with # are skipped.
+ */
+pub fn read_tum_trajectory<F: Float>(path_str: &str) -> std::io::Result<Vec<(F,Isometry3<F>)>> {
+    fs::read_to_string(path_str)?.lines()
+        .map(|l| l.trim())
+        .filter(|l| !l.is_empty() && !l.starts_with('#'))
+        .map(|line| {
+            let values = line.split_whitespace()
+                .map(|v| v.parse::<F>().map_err(|_| Error::new(ErrorKind::InvalidData, format!("Invalid number in line: {}", line))))
+                .collect::<std::io::Result<Vec<F>>>()?;
+            if values.len() != TUM_COLUMNS {
+                return Err(Error::new(ErrorKind::InvalidData, format!("Expected {} columns in line: {}", TUM_COLUMNS, line)));
+            }
+            let rotation = UnitQuaternion::from_quaternion(Quaternion::new(values[7], values[4], values[5], values[6]));
+            Ok((values[0], Isometry3::from_parts(Translation3::new(values[1], values[2], values[3]), rotation)))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use na::Matrix3x4;
+    use crate::camera::Resolution;
+    use crate::camera_model::Pinhole;
+    use crate::test_util::TempPath;
+
+    #[test]
+    fn camera_trajectory_round_trip() {
+        let model = Pinhole::from_focal_and_principal_point(-300.0, -300.0, 320.0, 240.0, 0.0);
+        // Epoch timestamps at 30 Hz need all significant digits of an f64
+        let timestamps = [Some(1403636579.0 + 2.0/30.0), None, Some(1403636579.0), Some(1403636579.0 + 1.0/30.0)];
+        let camera_features = timestamps.iter().enumerate().map(|(cam_id, timestamp)| {
+            let view = Isometry3::new(na::Vector3::new(0.1*cam_id as f64, -0.3, 1.0/3.0), na::Vector3::new(0.05, -0.2*cam_id as f64, 0.3));
+            let view_matrix: Matrix3x4<f64> = view.to_matrix().fixed_view::<3,4>(0,0).into_owned();
+            CameraFeatures::new(HashMap::new(), cam_id, view_matrix, &model, Resolution::new(640, 480)).with_timestamp(*timestamp)
+        }).collect::<Vec<_>>();
+
+        let path = TempPath::new("trajectory.tum");
+        write_tum_camera_trajectory(&path, &camera_features).unwrap();
+        let trajectory = read_tum_trajectory::<f64>(&path).unwrap();
+
+        assert_eq!(trajectory.len(), 3);
+        for ((time, pose), cam_id) in trajectory.iter().zip([2, 3, 0]) {
+            assert_eq!(Some(*time), timestamps[cam_id]);
+            assert!((pose.to_homogeneous() - camera_features[cam_id].get_pose().to_homogeneous()).norm() < 1e-12);
+        }
+    }
+
+    #[test]
+    fn malformed_lines_are_rejected() {
+        for content in ["1.0 0 0 0 0 0 0\n", "1.0 0 0 0 0 0 0 one\n"] {
+            let path = TempPath::new("malformed.tum");
+            fs::write(&path, content).unwrap();
+            assert_eq!(read_tum_trajectory::<f64>(&path).unwrap_err().kind(), ErrorKind::InvalidData);
+        }
+    }
+}
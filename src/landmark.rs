@@ -1,42 +1,39 @@
 extern crate nalgebra as na;
 
 use na::Vector3;
+use crate::float::Float;
+use crate::schema::{LandmarksDocument,LandmarkSerial,SCHEMA_VERSION};
 
-#[derive(Debug,PartialEq)]
-pub struct Landmark {
+#[derive(Debug,Clone,PartialEq)]
+pub struct Landmark<F: Float> {
     id: usize,
-    position: Vector3<f32>
+    position: Vector3<F>
 }
 
-impl Landmark {
-    pub fn new(id: &usize, position: &Vector3<f32>) -> Landmark {
+impl<F: Float> Landmark<F> {
+    pub fn new(id: &usize, position: &Vector3<F>) -> Landmark<F> {
         Landmark {
-            id: id.clone(),
-            position: position.clone()
+            id: *id,
+            position: *position
         }
     }
     pub fn get_id(&self) -> &usize {&self.id}
-    pub fn get_position(&self) -> &Vector3<f32> {&self.position}
-    pub fn to_serial(landmark_vec: &Vec<Landmark>) -> Vec<(usize, [f32;3])> {
-        landmark_vec.into_iter().map(|l|{
+    pub fn get_position(&self) -> &Vector3<F> {&self.position}
+    pub fn to_serial(landmark_vec: &[Landmark<F>]) -> LandmarksDocument<F> {
+        let landmarks = landmark_vec.iter().map(|l|{
             let pos = l.get_position();
-            (l.id, [pos.x,pos.y,pos.z] )
-        }).collect::<Vec<_>>()
+            LandmarkSerial {id: l.id, position: [pos.x,pos.y,pos.z]}
+        }).collect::<Vec<_>>();
+        LandmarksDocument {version: SCHEMA_VERSION, landmarks}
     }
 
-    pub fn from_serial(serial: &Vec<(usize, [f32;3])>) -> Vec<Landmark> {
-        serial.into_iter().map(|s| {
-            let id = s.0;
-            let pos = &s.1;
-
+    pub fn from_serial(serial: &LandmarksDocument<F>) -> Vec<Landmark<F>> {
+        serial.landmarks.iter().map(|s| {
+            let pos = &s.position;
             Landmark {
-                id,
-                position: Vector3::<f32>::new(pos[0],pos[1],pos[2])
+                id: s.id,
+                position: Vector3::<F>::new(pos[0],pos[1],pos[2])
             }
-    
         }).collect::<Vec<_>>()
-
     }
 }
-
-
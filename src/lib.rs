@@ -1,112 +1,252 @@
 extern crate nalgebra as na;
 
+pub mod camera;
 pub mod camera_features;
+pub mod camera_model;
+pub mod convention;
+pub mod dataset;
+pub mod feature;
 pub mod landmark;
 pub mod filter;
+pub mod float;
+pub mod framing;
 pub mod io;
 pub mod gltf;
+pub mod imu;
+pub mod intrinsics;
 pub mod obj;
+pub mod perturbation;
+pub mod planning;
 pub mod rasterizer;
+pub mod rig;
+pub mod rolling_shutter;
+pub mod scenario;
+pub mod schema;
+pub mod sequence;
+pub mod spline;
+pub mod trajectory;
 pub mod triangle;
+#[cfg(test)]
+mod test_util;
 
 use std::iter::zip;
 use std::collections::HashMap;
-use na::{Vector2,Vector3,Matrix3,Matrix4xX,Matrix3x4, Matrix3xX, Point3};
+use na::{Vector2,Vector3,Matrix4xX,Matrix3x4, Point3};
 use triangle::Triangle;
 use ordered_float::OrderedFloat;
+use float::Float;
+use camera_model::CameraModel;
 
 /**
- * Returns A vector of indexed points in image space where the index represents the column of the corresponding 3D point matrix in camera space
+ * Landmark id and image position of a projected point, None if the point could not be projected
  */
-pub fn project_points(indexed_landmarks: &Vec<landmark::Landmark>, intrinsic_matrix: &Matrix3<f32>, view_matrix: &Matrix3x4<f32>) -> (Vec<(usize,Vector2<f32>)>, Matrix3xX<f32>) {
-    let mut ps = Matrix4xX::<f32>::from_element(indexed_landmarks.len(), 1.0);
-    let mut vec_id_map = HashMap::<(OrderedFloat<f32>,OrderedFloat<f32>,OrderedFloat<f32>),usize>::with_capacity(indexed_landmarks.len());
-
-    for i in 0..indexed_landmarks.len() {
-        let l = &indexed_landmarks[i];
-        let p = l.get_position();
-        let key = (OrderedFloat(p.x),OrderedFloat(p.y),OrderedFloat(p.z));
-        if !vec_id_map.contains_key(&key) {
-            vec_id_map.insert(key,*l.get_id());
-        }   
-        ps.fixed_view_mut::<3,1>(0,i).copy_from(p);
+pub type ProjectedPoint<F> = Option<(usize,Vector2<F>)>;
+
+/**
+ * Returns a vector of indexed points in image space aligned with the landmarks, None if the camera model can not project the point.
+ * Also returns the depth of every landmark as defined by the camera model.
+ */
+pub fn project_points<F: Float, C: CameraModel<F>>(indexed_landmarks: &[landmark::Landmark<F>], camera_model: &C, view_matrix: &Matrix3x4<F>) -> (Vec<ProjectedPoint<F>>, Vec<F>) {
+    let mut ps = Matrix4xX::<F>::from_element(indexed_landmarks.len(), F::one());
+    for (i,l) in indexed_landmarks.iter().enumerate() {
+        ps.fixed_view_mut::<3,1>(0,i).copy_from(l.get_position());
     }
     let points_cam = view_matrix*(&ps);
-    let projected_points = intrinsic_matrix*&points_cam;
-
-    let screen_points_with_idx = projected_points.column_iter().enumerate()
-        .map(|(i,c)|{
-            let orig_p = ps.column(i);
-            let key = (OrderedFloat(orig_p.x),OrderedFloat(orig_p.y),OrderedFloat(orig_p.z));
-            (*vec_id_map.get(&key).expect("point not present in key map"),c)
-        } )
-        .filter(|(_,c)| c.z != 0.0)
-        .map(|(i,c)| (i,(c.x/c.z,c.y/c.z)))
-        .map(|(i,(x,y))| (i,Vector2::new(x, y)))
+
+    let screen_points_with_idx = zip(deduplicated_ids(indexed_landmarks),points_cam.column_iter())
+        .map(|(id,c)| camera_model.project(&c.into_owned()).map(|p| (id,p)))
         .collect::<Vec<_>>();
-    (screen_points_with_idx, points_cam)
+    let depths = points_cam.column_iter().map(|c| camera_model.depth(&c.into_owned())).collect::<Vec<_>>();
+    (screen_points_with_idx, depths)
+}
+
+/**
+ * Landmarks sharing a position are reported with the id of the first of them, aligned with the landmarks
+ */
+pub(crate) fn deduplicated_ids<F: Float>(indexed_landmarks: &[landmark::Landmark<F>]) -> Vec<usize> {
+    let mut vec_id_map = HashMap::<(OrderedFloat<f64>,OrderedFloat<f64>,OrderedFloat<f64>),usize>::with_capacity(indexed_landmarks.len());
+    // Both f32 and f64 are exactly representable as f64 keys
+    let to_key = |p: &Vector3<F>| (OrderedFloat(float::to_f64(p.x)),OrderedFloat(float::to_f64(p.y)),OrderedFloat(float::to_f64(p.z)));
+    indexed_landmarks.iter().map(|l| *vec_id_map.entry(to_key(l.get_position())).or_insert(*l.get_id())).collect()
 }
 
-pub fn group_points_to_triangles(pixels_with_id: &Vec<(usize,Vector2<f32>)>, cam_points: &Matrix3xX<f32>) -> Vec<(Triangle<2>,Triangle<3>)> {
-    (0..pixels_with_id.len()-2).step_by(3).map(|i| {
-        let (id_0,pix_v0) = pixels_with_id[i];
-        let (id_1,pix_v1) = pixels_with_id[i+1];
-        let (id_2,pix_v2) = pixels_with_id[i+2];
-        let cam_point_v0 = cam_points.column(id_0);
-        let cam_point_v1 = cam_points.column(id_1);
-        let cam_point_v2 = cam_points.column(id_2);
-        (
-            Triangle::from_vec(&pix_v0.cast::<f32>(), Some(id_0) ,&pix_v1.cast::<f32>(), Some(id_1), &pix_v2.cast::<f32>(), Some(id_2)),
-            Triangle::from_view(&cam_point_v0, Some(id_0), &cam_point_v1, Some(id_1), &cam_point_v2, Some(id_2))
-        )
+/**
+ * Groups consecutive points into triangles paired with the depths of their vertices.
+ * Triangles with a vertex that could not be projected or that are degenerate on screen are skipped.
+ * If the image repeats horizontally, triangles spanning more than half the period cross the seam and are emitted once on each side.
+ */
+pub fn group_points_to_triangles<F: Float>(pixels_with_id: &[ProjectedPoint<F>], depths: &[F], horizontal_period: Option<F>) -> Vec<(Triangle<F,2>,Vector3<F>)> {
+    assert_eq!(pixels_with_id.len(), depths.len());
+    (0..pixels_with_id.len()/3).map(|t| 3*t).filter_map(|i| {
+        let (id_0,pix_v0) = pixels_with_id[i]?;
+        let (id_1,pix_v1) = pixels_with_id[i+1]?;
+        let (id_2,pix_v2) = pixels_with_id[i+2]?;
+        Some(([(id_0,pix_v0),(id_1,pix_v1),(id_2,pix_v2)], Vector3::new(depths[i],depths[i+1],depths[i+2])))
+    }).flat_map(|(vertices,vertex_depths)| {
+        let half_period = horizontal_period.map(|period| period/float::from_f64(2.0));
+        let u_min = vertices.iter().map(|(_,v)| v.x).fold(vertices[0].1.x, |a,b| a.min(b));
+        let u_max = vertices.iter().map(|(_,v)| v.x).fold(vertices[0].1.x, |a,b| a.max(b));
+        let copies = match (horizontal_period,half_period) {
+            (Some(period),Some(half_period)) if u_max - u_min > half_period => vec![
+                vertices.map(|(id,v)| (id, match v.x < u_max - half_period {true => Vector2::new(v.x + period, v.y), false => v})),
+                vertices.map(|(id,v)| (id, match v.x > u_min + half_period {true => Vector2::new(v.x - period, v.y), false => v}))
+            ],
+            _ => vec![vertices]
+        };
+        copies.into_iter()
+            .filter(|[(_,v0),(_,v1),(_,v2)]| v0 != v1 && v0 != v2 && v1 != v2)
+            .map(move |[(id_0,v0),(id_1,v1),(id_2,v2)]| (Triangle::from_vec(&v0, Some(id_0), &v1, Some(id_1), &v2, Some(id_2)), vertex_depths))
     }).collect::<Vec<_>>()
 }
 
-pub fn filter_screen_points_for_camera_views(indexed_landmarks: &Vec<landmark::Landmark>, intrinsic_matrix: &Matrix3<f32>, view_matrices: &Vec<Matrix3x4<f32>>, screen_width: f32, screen_height: f32, filter_type: filter::FilterType) -> Vec<Vec<(usize,Vector2<usize>)>> {
-    view_matrices.iter().map(|view_matrix| {
-        let (points_screen_with_idx, points_cam) = project_points(indexed_landmarks, &intrinsic_matrix, &view_matrix.fixed_view::<3,4>(0, 0).into_owned());
-        match filter_type {
-            filter::FilterType::Depth => filter::filter_visible_screen_points_by_depth(&points_screen_with_idx,&points_cam),
-            filter::FilterType::Rasterizer => {
-                let screen_cam_triangles = group_points_to_triangles(&points_screen_with_idx,&points_cam);
-                filter::filter_visible_screen_points_by_rasterizer(&screen_cam_triangles,screen_width, screen_height)
-            }
+/**
+ * Returns the visible features of a single view. Features outside of the view's resolution are discarded.
+ * Every feature carries its bearing vector obtained by unprojecting its position.
+ */
+pub fn filter_screen_points_for_camera_view<F: Float, C: CameraModel<F>>(indexed_landmarks: &[landmark::Landmark<F>], camera: &camera::Camera<F,C>, filter_type: filter::FilterType) -> Vec<(usize,feature::Feature<F>)> {
+    let (points_screen_with_idx, depths) = project_points(indexed_landmarks, camera.get_camera_model(), &camera.get_view_matrix());
+    filter_projected_points(&points_screen_with_idx, &depths, camera.get_description(), filter_type)
+}
+
+/**
+ * Visibility filtering of already projected points. Points and depths are aligned as returned by project_points.
+ */
+pub fn filter_projected_points<F: Float, C: CameraModel<F>>(points_screen_with_idx: &[ProjectedPoint<F>], depths: &[F], camera: &camera::CameraDescription<F,C>, filter_type: filter::FilterType) -> Vec<(usize,feature::Feature<F>)> {
+    let resolution = camera.get_resolution();
+    let visible_screen_points = match filter_type {
+        filter::FilterType::Depth => filter::filter_visible_screen_points_by_depth(points_screen_with_idx,depths),
+        filter::FilterType::Rasterizer => {
+            let screen_triangles = group_points_to_triangles(points_screen_with_idx,depths,camera.get_camera_model().horizontal_period());
+            filter::filter_visible_screen_points_by_rasterizer(&screen_triangles,camera.get_camera_model().depth_interpolation(),resolution.get_width(), resolution.get_height())
         }
+    };
+    visible_screen_points.into_iter()
+        .filter(|(_,f)| camera.contains_pixel(f.get_pixel()))
+        .map(|(id,f)| (id, f.with_bearing(camera.get_camera_model().unproject(f.get_position()))))
+        .collect()
+}
+
+/**
+ * Visible features of every camera, aligned with the cameras
+ */
+pub fn filter_screen_points_for_camera_views<F: Float, C: CameraModel<F>>(indexed_landmarks: &[landmark::Landmark<F>], cameras: &[camera::Camera<F,C>], filter_type: filter::FilterType) -> Vec<Vec<(usize,feature::Feature<F>)>> {
+    cameras.iter().map(|camera| {
+        filter_screen_points_for_camera_view(indexed_landmarks, camera, filter_type)
     }).collect::<Vec<_>>()
 }
 
+/**
+ * Lazily projects, filters and matches one view at a time. Memory use is bounded by a single view,
+ * which allows writing very long sequences with io::CameraFeaturesWriter.
+ */
+pub fn generate_camera_features_iter<'a, F: Float, C: CameraModel<F>>(indexed_landmarks: &'a [landmark::Landmark<F>], cameras: &'a [camera::Camera<F,C>], filter_type: filter::FilterType) -> impl Iterator<Item=camera_features::CameraFeatures<F>> + 'a {
+    cameras.iter().map(move |camera| {
+        let screen_points_with_id = filter_screen_points_for_camera_view(indexed_landmarks, camera, filter_type);
+        let point_map = screen_points_with_id.into_iter().collect::<HashMap<usize,feature::Feature<F>>>();
+        camera_features::CameraFeatures::from_camera(point_map,camera)
+    })
+}
 
-pub fn generate_matches(view_matrices: &Vec<Matrix3x4<f32>>, intrinsic_matrices: &Vec<Matrix3<f32>>, features: &Vec<Vec<(usize,Vector2<usize>)>>) -> Vec<camera_features::CameraFeatures> {
-    assert_eq!(view_matrices.len(), features.len());
-    zip(zip(view_matrices,intrinsic_matrices),features).enumerate().map(|(cam_id,((view_matrix,intrinsic_matrix),screen_points_with_id))| {
-        let point_map = screen_points_with_id.into_iter().map(|&(k,v)| (k,v)).collect::<HashMap<usize,Vector2<usize>>>();
-        camera_features::CameraFeatures::new(point_map,cam_id,view_matrix.clone(),intrinsic_matrix.clone()) 
+/**
+ * Pairs the features of every camera, as returned by filter_screen_points_for_camera_views, with the camera
+ */
+pub fn generate_matches<F: Float, C: CameraModel<F>>(cameras: &[camera::Camera<F,C>], features: &[Vec<(usize,feature::Feature<F>)>]) -> Vec<camera_features::CameraFeatures<F>> {
+    assert_eq!(cameras.len(), features.len());
+    zip(cameras,features).map(|(camera,screen_points_with_id)| {
+        let point_map = screen_points_with_id.iter().copied().collect::<HashMap<usize,feature::Feature<F>>>();
+        camera_features::CameraFeatures::from_camera(point_map,camera)
     }).collect()
 }
 
-pub fn generate_landmarks(indexed_points: &Vec<(usize,Vector3<f32>)>) -> Vec<landmark::Landmark> {
+pub fn generate_landmarks<F: Float>(indexed_points: &[(usize,Vector3<F>)]) -> Vec<landmark::Landmark<F>> {
     indexed_points.iter().map(|(i,p)| {
         landmark::Landmark::new(i,p) 
     }).collect()
 }
 
-pub fn generate_camera_trajectory(start: &Point3<f32>, target: &Point3<f32>, arc_angle: f32, step_count: usize) -> Vec<Point3<f32>> {
-    assert!(arc_angle > 0.0 && arc_angle <= 360.0);
+/**
+ * Positions on a horizontal arc of arc_angle degrees around target starting at start, keeping the height of start.
+ * Returns step_count+1 positions. See the trajectory module for poses and other trajectory types.
+ */
+pub fn generate_camera_trajectory<F: Float>(start: &Point3<F>, target: &Point3<F>, arc_angle: F, step_count: usize) -> Vec<Point3<F>> {
+    assert!(arc_angle > F::zero() && arc_angle <= float::from_f64(360.0));
     let pos = start-target;
-    let r = pos.iter().map(|v| v.powi(2)).sum::<f32>().sqrt();
+    let r = pos.norm();
     let theta = (pos.y/r).acos();
     let phi = pos.z.atan2(pos.x);
-    let arc_angle_rad = arc_angle * std::f32::consts::PI/180.0;
+    let arc_angle_rad = arc_angle * F::pi()/float::from_f64(180.0);
 
-    (0..step_count+1).map(|s|{
-        let ratio = (s as f32) / (step_count as f32);
+    trajectory::ratios::<F>(step_count).map(|ratio|{
         let rad_offset = ratio*arc_angle_rad;
         let p = phi + rad_offset;
         let x_new = target.x + r*theta.sin()*p.cos();
-        let y_new = target.y;
-        let z_new = target.x + r*theta.sin()*p.sin();
-        Point3::<f32>::new(x_new,y_new,z_new)
+        let y_new = target.y + r*theta.cos();
+        let z_new = target.z + r*theta.sin()*p.sin();
+        Point3::<F>::new(x_new,y_new,z_new)
     }).collect()
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use na::{Isometry3,Translation3,UnitQuaternion};
+    use camera::{Camera,CameraDescription,Resolution};
+    use camera_model::Pinhole;
+    use filter::FilterType;
+
+    // Triangles of a UV sphere of radius 1 around the origin, three consecutive vertices per triangle
+    fn sphere_triangles() -> Vec<Vector3<f64>> {
+        let (rings, segments) = (12, 24);
+        let vertex = |ring: usize, segment: usize| {
+            let (theta, phi) = (std::f64::consts::PI*ring as f64/rings as f64, 2.0*std::f64::consts::PI*segment as f64/segments as f64);
+            Vector3::new(theta.sin()*phi.cos(), theta.cos(), theta.sin()*phi.sin())
+        };
+        (0..rings).flat_map(|r| (0..segments).flat_map(move |s| [vertex(r,s), vertex(r+1,s), vertex(r+1,s+1), vertex(r,s), vertex(r+1,s+1), vertex(r,s+1)])).collect()
+    }
+
+    fn camera_features<F: Float>(filter_type: FilterType) -> camera_features::CameraFeatures<F> {
+        let to_f = |v: f64| float::from_f64::<F>(v);
+        let points = sphere_triangles().iter().enumerate().map(|(i,p)| (i, p.map(to_f))).collect::<Vec<_>>();
+        let landmarks = generate_landmarks(&points);
+        let pose = Isometry3::from_parts(Translation3::new(to_f(0.2), to_f(0.1), to_f(4.0)), UnitQuaternion::from_euler_angles(to_f(0.05), to_f(-0.03), to_f(0.1)));
+        let model = Pinhole::from_focal_and_principal_point(to_f(-300.0), to_f(-300.0), to_f(160.0), to_f(120.0), F::zero());
+        let cameras = vec![Camera::new(0, "camera", pose, CameraDescription::new(model, Resolution::new(320, 240)))];
+        let camera_features = generate_camera_features_iter(&landmarks, &cameras, filter_type).next().unwrap();
+        camera_features
+    }
+
+    #[test]
+    fn f32_matches_f64() {
+        for filter_type in [FilterType::Depth, FilterType::Rasterizer] {
+            let (single, double) = (camera_features::<f32>(filter_type), camera_features::<f64>(filter_type));
+            let (single_map, double_map) = (single.get_feature_map(), double.get_feature_map());
+            let common = single_map.keys().filter(|id| double_map.contains_key(id)).count();
+            assert!(common > 50, "{:?}: {} common of {} and {}", filter_type, common, single_map.len(), double_map.len());
+            // Visibility may only differ for points on the silhouette where the precisions round differently
+            assert!(single_map.len() - common <= double_map.len()/50 && double_map.len() - common <= double_map.len()/50, "{:?}: {} common of {} and {}", filter_type, common, single_map.len(), double_map.len());
+            for (id,feature) in single_map.iter().filter(|(id,_)| double_map.contains_key(id)) {
+                let position = feature.get_position().map(|v| v as f64);
+                assert!((position - double_map[id].get_position()).norm() < 1e-3);
+            }
+        }
+    }
+
+    #[test]
+    fn camera_trajectory_arc() {
+        let (start, target) = (Point3::<f64>::new(3.0, 1.5, 1.0), Point3::new(1.0, -0.5, -2.0));
+        let horizontal_radius = (start - target).xz().norm();
+        let positions = generate_camera_trajectory(&start, &target, 90.0, 6);
+        assert_eq!(positions.len(), 7);
+        assert!((positions[0] - start).norm() < 1e-9);
+        for position in &positions {
+            assert!((position.y - start.y).abs() < 1e-9);
+            assert!(((position - target).xz().norm() - horizontal_radius).abs() < 1e-9);
+        }
+        let (first, last) = ((positions[0] - target).xz(), (positions[6] - target).xz());
+        assert!((first.angle(&last) - std::f64::consts::FRAC_PI_2).abs() < 1e-9);
 
+        let single = generate_camera_trajectory(&start, &target, 90.0, 0);
+        assert_eq!(single.len(), 1);
+        assert!((single[0] - start).norm() < 1e-9);
+    }
+}
@@ -1,15 +1,17 @@
 extern crate nalgebra as na;
 
 use na::Vector3;
+use crate::float::{self,Float};
 
 pub fn load(path: &str) -> obj::Obj {
     obj::Obj::load(path).unwrap()
 }
 
-pub fn load_vertex_positions(models: &obj::Obj) -> Vec<Vec<Vector3<f32>>> {
+pub fn load_vertex_positions<F: Float>(models: &obj::Obj) -> Vec<Vec<Vector3<F>>> {
+    let to_vec3 = |p: [f32;3]| Vector3::new(float::from_f64(p[0] as f64),float::from_f64(p[1] as f64),float::from_f64(p[2] as f64));
     models.data.objects.iter().map(|o| {
-        o.groups.iter().map(|g| {
-            g.polys.iter().map(|p| {
+        o.groups.iter().flat_map(|g| {
+            g.polys.iter().flat_map(|p| {
                 let i1 = p.0[0].0;
                 let i2 = p.0[1].0;
                 let i3 = p.0[2].0;
@@ -18,12 +20,9 @@ pub fn load_vertex_positions(models: &obj::Obj) -> Vec<Vec<Vector3<f32>>> {
                 let p1 = models.data.position[i2];
                 let p2 = models.data.position[i3];
 
-                vec!(
-                    Vector3::new(p0[0],p0[1],p0[2]),
-                    Vector3::new(p1[0],p1[1],p1[2]),
-                    Vector3::new(p2[0],p2[1],p2[2]))
-            }).flatten().collect::<Vec<_>>()
-        }).flatten().collect::<Vec<_>>()
+                [to_vec3(p0), to_vec3(p1), to_vec3(p2)]
+            }).collect::<Vec<_>>()
+        }).collect::<Vec<_>>()
     }).collect::<Vec<_>>()
 
 }
\ No newline at end of file
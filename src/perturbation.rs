@@ -0,0 +1,210 @@
+extern crate nalgebra as na;
+
+use na::{Vector3,Matrix3x4,Isometry3,UnitQuaternion,Translation3};
+use rand::Rng;
+use rand_distr::StandardNormal;
+use crate::camera_features::CameraFeatures;
+use crate::camera_model::{CameraModel,AnyCameraModel};
+use crate::dataset::Dataset;
+use crate::float::{self,Float};
+use crate::landmark::Landmark;
+use crate::schema::PerturbationSerial;
+
+/**
+ * Standard deviations of the Gaussian noise used to turn ground truth into a noisy initialisation, e.g. for bundle adjustment.
+ * Rotations are perturbed in the tangent space of the camera frame, R' = R*Exp(w), camera centres and landmarks in world units
+ * and the focal lengths and principal point in pixels. The focal noise is shared by fx and fy to keep the aspect ratio.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct Perturbation<F: Float> {
+    rotation_sigma: F,
+    translation_sigma: F,
+    focal_sigma: F,
+    principal_point_sigma: F,
+    landmark_sigma: F
+}
+
+impl<F: Float> Default for Perturbation<F> {
+    fn default() -> Perturbation<F> {
+        Perturbation::new()
+    }
+}
+
+impl<F: Float> Perturbation<F> {
+    /**
+     * No noise, set the components with the builder methods
+     */
+    pub fn new() -> Perturbation<F> {
+        Perturbation {rotation_sigma: F::zero(), translation_sigma: F::zero(), focal_sigma: F::zero(), principal_point_sigma: F::zero(), landmark_sigma: F::zero()}
+    }
+
+    /**
+     * Radians
+     */
+    pub fn rotation_sigma(self, rotation_sigma: F) -> Perturbation<F> {
+        Perturbation {rotation_sigma, ..self}
+    }
+
+    pub fn translation_sigma(self, translation_sigma: F) -> Perturbation<F> {
+        Perturbation {translation_sigma, ..self}
+    }
+
+    /**
+     * Pixels. Has to be small compared to the focal length, which keeps its sign
+     */
+    pub fn focal_sigma(self, focal_sigma: F) -> Perturbation<F> {
+        Perturbation {focal_sigma, ..self}
+    }
+
+    /**
+     * Pixels
+     */
+    pub fn principal_point_sigma(self, principal_point_sigma: F) -> Perturbation<F> {
+        Perturbation {principal_point_sigma, ..self}
+    }
+
+    pub fn landmark_sigma(self, landmark_sigma: F) -> Perturbation<F> {
+        Perturbation {landmark_sigma, ..self}
+    }
+
+    pub fn get_rotation_sigma(&self) -> F {self.rotation_sigma}
+    pub fn get_translation_sigma(&self) -> F {self.translation_sigma}
+    pub fn get_focal_sigma(&self) -> F {self.focal_sigma}
+    pub fn get_principal_point_sigma(&self) -> F {self.principal_point_sigma}
+    pub fn get_landmark_sigma(&self) -> F {self.landmark_sigma}
+
+    /**
+     * Copy with a perturbed pose and intrinsics. The features are the exact ground truth observations.
+     * Models without a focal length and principal point, or unknown models, keep their parameters.
+     */
+    pub fn perturb_camera_features<R: Rng>(&self, camera_features: &CameraFeatures<F>, rng: &mut R) -> CameraFeatures<F> {
+        let pose = camera_features.get_pose();
+        let rotation = pose.rotation*UnitQuaternion::from_scaled_axis(gaussian_vector(rng, self.rotation_sigma));
+        let translation = pose.translation.vector + gaussian_vector(rng, self.translation_sigma);
+        let view = Isometry3::from_parts(Translation3::from(translation), rotation).inverse();
+        let view_matrix: Matrix3x4<F> = view.to_matrix().fixed_view::<3,4>(0,0).into_owned();
+        let focal_noise = gaussian(rng, self.focal_sigma);
+        let principal_point_noise = [gaussian(rng, self.principal_point_sigma), gaussian(rng, self.principal_point_sigma)];
+
+        let perturbed = camera_features.clone().with_view_matrix(view_matrix);
+        let Some(model) = camera_features.get_camera_model::<AnyCameraModel<F>>() else {return perturbed};
+        let Some((focal_indices, principal_point_indices)) = model.intrinsic_indices() else {return perturbed};
+        let mut parameters = model.get_parameters();
+        for i in focal_indices {
+            let sign = parameters[i].signum();
+            parameters[i] += sign*focal_noise;
+        }
+        for (i,noise) in principal_point_indices.into_iter().zip(principal_point_noise) {
+            parameters[i] += noise;
+        }
+        let model = AnyCameraModel::from_parameters(model.name(), &parameters).expect("Perturbed intrinsics are invalid");
+        perturbed.with_camera_model(&model)
+    }
+
+    pub fn perturb_landmarks<R: Rng>(&self, landmarks: &[Landmark<F>], rng: &mut R) -> Vec<Landmark<F>> {
+        landmarks.iter().map(|l| Landmark::new(l.get_id(), &(l.get_position() + gaussian_vector(rng, self.landmark_sigma)))).collect()
+    }
+
+    /**
+     * Perturbs all cameras, then all landmarks. The same seed reproduces the same initialisation.
+     */
+    pub fn perturb_dataset<R: Rng>(&self, dataset: &Dataset<F>, rng: &mut R) -> Initialisation<F> {
+        let camera_features = dataset.get_camera_features().iter().map(|cf| self.perturb_camera_features(cf, rng)).collect();
+        let landmarks = self.perturb_landmarks(dataset.get_landmarks(), rng);
+        Initialisation {camera_features, landmarks, perturbation: *self}
+    }
+
+    pub fn to_serial(&self) -> PerturbationSerial {
+        PerturbationSerial {
+            rotation_sigma: float::to_f64(self.rotation_sigma),
+            translation_sigma: float::to_f64(self.translation_sigma),
+            focal_sigma: float::to_f64(self.focal_sigma),
+            principal_point_sigma: float::to_f64(self.principal_point_sigma),
+            landmark_sigma: float::to_f64(self.landmark_sigma)
+        }
+    }
+
+    pub fn from_serial(serial: &PerturbationSerial) -> Perturbation<F> {
+        Perturbation {
+            rotation_sigma: float::from_f64(serial.rotation_sigma),
+            translation_sigma: float::from_f64(serial.translation_sigma),
+            focal_sigma: float::from_f64(serial.focal_sigma),
+            principal_point_sigma: float::from_f64(serial.principal_point_sigma),
+            landmark_sigma: float::from_f64(serial.landmark_sigma)
+        }
+    }
+}
+
+/**
+ * Perturbed copy of a dataset's cameras and landmarks, saved next to the ground truth by Dataset::save
+ */
+#[derive(Debug,Clone,PartialEq)]
+pub struct Initialisation<F: Float> {
+    camera_features: Vec<CameraFeatures<F>>,
+    landmarks: Vec<Landmark<F>>,
+    perturbation: Perturbation<F>
+}
+
+impl<F: Float> Initialisation<F> {
+    pub fn new(camera_features: Vec<CameraFeatures<F>>, landmarks: Vec<Landmark<F>>, perturbation: Perturbation<F>) -> Initialisation<F> {
+        Initialisation {camera_features, landmarks, perturbation}
+    }
+
+    pub fn get_camera_features(&self) -> &Vec<CameraFeatures<F>> {&self.camera_features}
+    pub fn get_landmarks(&self) -> &Vec<Landmark<F>> {&self.landmarks}
+    pub fn get_perturbation(&self) -> &Perturbation<F> {&self.perturbation}
+}
+
+fn gaussian<F: Float, R: Rng>(rng: &mut R, sigma: F) -> F {
+    float::from_f64::<F>(rng.sample::<f64,_>(StandardNormal))*sigma
+}
+
+fn gaussian_vector<F: Float, R: Rng>(rng: &mut R, sigma: F) -> Vector3<F> {
+    Vector3::from_fn(|_,_| gaussian(rng, sigma))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::camera::{CameraDescription,Resolution};
+    use crate::camera_model::Pinhole;
+    use crate::scenario::ScenarioGenerator;
+
+    fn dataset() -> Dataset<f64> {
+        let description = CameraDescription::new(Pinhole::from_focal_and_principal_point(-300.0, -300.0, 320.0, 240.0, 0.0), Resolution::new(640, 480));
+        ScenarioGenerator::new(description).landmark_count(30).tiny_baseline(1.0, &mut StdRng::seed_from_u64(0)).to_dataset().unwrap()
+    }
+
+    fn focal_lengths(camera_features: &CameraFeatures<f64>) -> Vec<f64> {
+        camera_features.get_camera_parameters()[..2].to_vec()
+    }
+
+    #[test]
+    fn same_seed_reproduces_the_initialisation() {
+        let (dataset, perturbation) = (dataset(), Perturbation::new().rotation_sigma(0.01).translation_sigma(0.1).focal_sigma(5.0).principal_point_sigma(2.0).landmark_sigma(0.05));
+        let initialisation = perturbation.perturb_dataset(&dataset, &mut StdRng::seed_from_u64(42));
+        assert_eq!(perturbation.perturb_dataset(&dataset, &mut StdRng::seed_from_u64(42)), initialisation);
+        assert_ne!(perturbation.perturb_dataset(&dataset, &mut StdRng::seed_from_u64(43)), initialisation);
+        for (perturbed, ground_truth) in initialisation.get_camera_features().iter().zip(dataset.get_camera_features()) {
+            assert!(focal_lengths(perturbed).iter().all(|f| *f < 0.0));
+            assert_ne!(focal_lengths(perturbed), focal_lengths(ground_truth));
+        }
+    }
+
+    #[test]
+    fn zero_sigmas_keep_the_ground_truth() {
+        let dataset = dataset();
+        let initialisation = Perturbation::new().perturb_dataset(&dataset, &mut StdRng::seed_from_u64(7));
+        assert_eq!(initialisation.get_landmarks(), dataset.get_landmarks());
+        assert_eq!(initialisation.get_camera_features().len(), dataset.get_camera_features().len());
+        for (perturbed, ground_truth) in initialisation.get_camera_features().iter().zip(dataset.get_camera_features()) {
+            assert_eq!(perturbed.get_camera_parameters(), ground_truth.get_camera_parameters());
+            assert_eq!(perturbed.get_feature_map(), ground_truth.get_feature_map());
+            assert_eq!(perturbed.get_convention(), ground_truth.get_convention());
+            // The pose is inverted twice
+            assert!((perturbed.get_view_matrix() - ground_truth.get_view_matrix()).norm() < 1e-12);
+        }
+    }
+}
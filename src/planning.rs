@@ -0,0 +1,254 @@
+extern crate nalgebra as na;
+
+use std::collections::{HashMap,HashSet};
+use na::{Vector3,Isometry3};
+use crate::camera::{Camera,CameraDescription};
+use crate::camera_model::CameraModel;
+use crate::filter::FilterType;
+use crate::float::{self,Float};
+use crate::landmark::Landmark;
+use crate::{deduplicated_ids,filter_screen_points_for_camera_views};
+
+/**
+ * Cameras named candidate_{id} at the given camera to world poses, e.g. from trajectory::fibonacci_sphere moved onto a Framing
+ */
+pub fn candidates_from_poses<F: Float, C: CameraModel<F> + Clone>(poses: &[Isometry3<F>], description: &CameraDescription<F,C>) -> Vec<Camera<F,C>> {
+    poses.iter().enumerate().map(|(id,pose)| Camera::new(id, &format!("candidate_{}", id), *pose, description.clone())).collect()
+}
+
+/**
+ * A view selected by the planner. Every view but the first is connected to an earlier neighbour in the plan,
+ * with the fraction of its visible landmarks also seen by the neighbour and the median triangulation angle of those shared landmarks.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct PlannedView<F: Float> {
+    candidate_index: usize,
+    neighbour: Option<usize>,
+    overlap: F,
+    triangulation_angle: F
+}
+
+impl<F: Float> PlannedView<F> {
+    pub fn get_candidate_index(&self) -> usize {self.candidate_index}
+    /**
+     * Index of the neighbour in the plan
+     */
+    pub fn get_neighbour(&self) -> Option<usize> {self.neighbour}
+    pub fn get_overlap(&self) -> F {self.overlap}
+    pub fn get_triangulation_angle(&self) -> F {self.triangulation_angle}
+}
+
+#[derive(Debug,Clone,PartialEq)]
+pub struct ViewPlan<F: Float> {
+    views: Vec<PlannedView<F>>,
+    landmark_count: usize,
+    observed_landmark_count: usize,
+    triangulated_landmark_count: usize
+}
+
+impl<F: Float> ViewPlan<F> {
+    /**
+     * Views in the order they were selected
+     */
+    pub fn get_views(&self) -> &Vec<PlannedView<F>> {&self.views}
+    pub fn get_landmark_count(&self) -> usize {self.landmark_count}
+    pub fn get_observed_landmark_count(&self) -> usize {self.observed_landmark_count}
+    /**
+     * Landmarks seen by at least two planned views under the minimum triangulation angle
+     */
+    pub fn get_triangulated_landmark_count(&self) -> usize {self.triangulated_landmark_count}
+
+    pub fn get_candidate_indices(&self) -> Vec<usize> {
+        self.views.iter().map(|v| v.candidate_index).collect()
+    }
+
+    /**
+     * Fraction of unique landmarks that can be triangulated from the plan
+     */
+    pub fn coverage(&self) -> F {
+        match self.landmark_count {
+            0 => F::zero(),
+            n => float::from_usize::<F>(self.triangulated_landmark_count)/float::from_usize(n)
+        }
+    }
+
+    /**
+     * The planned cameras in plan order. Camera ids are those of the candidates.
+     */
+    pub fn select_cameras<C: CameraModel<F> + Clone>(&self, candidates: &[Camera<F,C>]) -> Vec<Camera<F,C>> {
+        self.views.iter().map(|v| candidates[v.candidate_index].clone()).collect()
+    }
+}
+
+/**
+ * Greedily selects candidate views to maximise the number of triangulable landmarks under a view budget.
+ * Candidates are scored with filter_screen_points_for_camera_views. A landmark counts as triangulable once two selected views
+ * observe it with rays enclosing at least the minimum triangulation angle, ties are broken by the number of newly observed landmarks.
+ * Every view after the first needs a selected neighbour which sees at least min_overlap of its visible landmarks, with a median
+ * triangulation angle over the shared landmarks of at least min_triangulation_angle. Planning stops early when no admissible view adds coverage.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct ViewPlanner<F: Float> {
+    view_budget: usize,
+    min_triangulation_angle: F,
+    min_overlap: F,
+    filter_type: FilterType
+}
+
+impl<F: Float> ViewPlanner<F> {
+    /**
+     * Defaults to a minimum triangulation angle of 2 degrees, an overlap of 30 percent and the rasterizer filter
+     */
+    pub fn new(view_budget: usize) -> ViewPlanner<F> {
+        ViewPlanner {
+            view_budget,
+            min_triangulation_angle: float::from_f64(2f64.to_radians()),
+            min_overlap: float::from_f64(0.3),
+            filter_type: FilterType::Rasterizer
+        }
+    }
+
+    /**
+     * Angle in radians
+     */
+    pub fn min_triangulation_angle(self, min_triangulation_angle: F) -> ViewPlanner<F> {
+        ViewPlanner {min_triangulation_angle, ..self}
+    }
+
+    /**
+     * Fraction between 0 and 1
+     */
+    pub fn min_overlap(self, min_overlap: F) -> ViewPlanner<F> {
+        ViewPlanner {min_overlap, ..self}
+    }
+
+    pub fn filter_type(self, filter_type: FilterType) -> ViewPlanner<F> {
+        ViewPlanner {filter_type, ..self}
+    }
+
+    pub fn get_view_budget(&self) -> usize {self.view_budget}
+    pub fn get_min_triangulation_angle(&self) -> F {self.min_triangulation_angle}
+    pub fn get_min_overlap(&self) -> F {self.min_overlap}
+    pub fn get_filter_type(&self) -> FilterType {self.filter_type}
+
+    pub fn plan<C: CameraModel<F>>(&self, indexed_landmarks: &[Landmark<F>], candidates: &[Camera<F,C>]) -> ViewPlan<F> {
+        let positions = deduplicated_ids(indexed_landmarks).into_iter().zip(indexed_landmarks.iter())
+            .map(|(id,l)| (id,*l.get_position()))
+            .collect::<HashMap<usize,Vector3<F>>>();
+        let visible = filter_screen_points_for_camera_views(indexed_landmarks, candidates, self.filter_type).into_iter()
+            .map(|features| features.into_iter().map(|(id,_)| id).collect::<HashSet<usize>>())
+            .collect::<Vec<_>>();
+        let centers = candidates.iter().map(|c| c.get_pose().translation.vector).collect::<Vec<_>>();
+        let ray_angle = |id: &usize, a: usize, b: usize| (centers[a] - positions[id]).angle(&(centers[b] - positions[id]));
+
+        let mut views = Vec::<PlannedView<F>>::with_capacity(self.view_budget);
+        let mut observers = HashMap::<usize,Vec<usize>>::new();
+        let mut triangulated = HashSet::<usize>::new();
+        while views.len() < self.view_budget {
+            let mut best: Option<((usize,usize),PlannedView<F>)> = None;
+            for (i,visible_i) in visible.iter().enumerate() {
+                if visible_i.is_empty() || views.iter().any(|v| v.candidate_index == i) {
+                    continue;
+                }
+                let view = match views.is_empty() {
+                    true => PlannedView {candidate_index: i, neighbour: None, overlap: F::zero(), triangulation_angle: F::zero()},
+                    false => match self.best_neighbour(i, &views, &visible, &ray_angle) {
+                        Some(view) => view,
+                        None => continue
+                    }
+                };
+                let newly_triangulated = visible_i.iter()
+                    .filter(|id| !triangulated.contains(id))
+                    .filter(|id| observers.get(id).is_some_and(|obs| obs.iter().any(|&j| ray_angle(id, i, j) >= self.min_triangulation_angle)))
+                    .count();
+                let newly_observed = visible_i.iter().filter(|id| !observers.contains_key(id)).count();
+                let gain = (newly_triangulated, newly_observed);
+                if gain != (0,0) && best.as_ref().is_none_or(|(best_gain,_)| gain > *best_gain) {
+                    best = Some((gain, view));
+                }
+            }
+            let Some((_,view)) = best else {break};
+            let i = view.candidate_index;
+            for id in visible[i].iter() {
+                let obs = observers.entry(*id).or_default();
+                if obs.iter().any(|&j| ray_angle(id, i, j) >= self.min_triangulation_angle) {
+                    triangulated.insert(*id);
+                }
+                obs.push(i);
+            }
+            views.push(view);
+        }
+
+        ViewPlan {
+            views,
+            landmark_count: positions.len(),
+            observed_landmark_count: observers.len(),
+            triangulated_landmark_count: triangulated.len()
+        }
+    }
+
+    /**
+     * The admissible planned view with the largest overlap with candidate i
+     */
+    fn best_neighbour<A: Fn(&usize, usize, usize) -> F>(&self, i: usize, views: &[PlannedView<F>], visible: &[HashSet<usize>], ray_angle: &A) -> Option<PlannedView<F>> {
+        views.iter().enumerate().filter_map(|(k,neighbour)| {
+            let j = neighbour.candidate_index;
+            let shared = visible[i].intersection(&visible[j]).collect::<Vec<_>>();
+            let overlap = float::from_usize::<F>(shared.len())/float::from_usize(visible[i].len());
+            if shared.is_empty() || overlap < self.min_overlap {
+                return None;
+            }
+            let mut angles = shared.into_iter().map(|id| ray_angle(id, i, j)).collect::<Vec<_>>();
+            angles.sort_by(|a,b| a.partial_cmp(b).expect("Angles have to be comparable"));
+            let triangulation_angle = angles[angles.len()/2];
+            (triangulation_angle >= self.min_triangulation_angle).then_some(PlannedView {candidate_index: i, neighbour: Some(k), overlap, triangulation_angle})
+        }).max_by(|a,b| a.overlap.partial_cmp(&b.overlap).expect("Overlaps have to be comparable"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use na::{Point3,Vector3};
+    use rand::{Rng,SeedableRng};
+    use rand::rngs::StdRng;
+    use crate::camera::Resolution;
+    use crate::camera_model::Pinhole;
+    use crate::trajectory;
+
+    #[test]
+    fn plan_respects_overlap_angle_and_budget() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let landmarks = (0..300).map(|id| Landmark::new(&id, &Vector3::from_fn(|_,_| rng.gen_range(-1.0..1.0)))).collect::<Vec<_>>();
+        let description = CameraDescription::new(Pinhole::from_focal_and_principal_point(-200.0, -200.0, 160.0, 120.0, 0.0), Resolution::new(320, 240));
+        let poses = trajectory::orbit(&Point3::origin(), 6.0, 0.3, 0.0, 2.0*std::f64::consts::PI*23.0/24.0, 23, &Vector3::y_axis());
+        let candidates = candidates_from_poses(&poses, &description);
+        let planner = ViewPlanner::new(6).min_overlap(0.5).min_triangulation_angle(10f64.to_radians()).filter_type(FilterType::Depth);
+
+        let visible = filter_screen_points_for_camera_views(&landmarks, &candidates, FilterType::Depth).into_iter()
+            .map(|features| features.into_iter().map(|(id,_)| id).collect::<HashSet<usize>>())
+            .collect::<Vec<_>>();
+        let plan = planner.plan(&landmarks, &candidates);
+        let views = plan.get_views();
+        assert!(views.len() > 1 && views.len() <= planner.get_view_budget());
+        assert_eq!(views[0].get_neighbour(), None);
+        for (k, view) in views.iter().enumerate().skip(1) {
+            let neighbour = view.get_neighbour().unwrap();
+            assert!(neighbour < k);
+            let (i, j) = (view.get_candidate_index(), views[neighbour].get_candidate_index());
+            let shared = visible[i].intersection(&visible[j]).count();
+            assert_eq!(view.get_overlap(), shared as f64/visible[i].len() as f64);
+            assert!(view.get_overlap() >= planner.get_min_overlap());
+            assert!(view.get_triangulation_angle() >= planner.get_min_triangulation_angle());
+        }
+
+        // The planner is greedy, a smaller budget plans a prefix of the same views
+        let coverages = (0..=planner.get_view_budget()).map(|budget| {
+            let smaller = ViewPlanner {view_budget: budget, ..planner}.plan(&landmarks, &candidates);
+            assert!(plan.get_candidate_indices().starts_with(&smaller.get_candidate_indices()));
+            smaller.coverage()
+        }).collect::<Vec<_>>();
+        assert!(coverages.windows(2).all(|w| w[0] <= w[1]), "{:?}", coverages);
+        assert!(plan.coverage() > 0.0);
+    }
+}
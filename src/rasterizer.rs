@@ -1,73 +1,77 @@
 extern crate nalgebra as na;
 
-use na::{Vector2, Matrix2};
+use na::{Vector2, Vector3, Matrix2};
 use crate::triangle::Triangle;
+use crate::float::{self,Float};
+use crate::camera_model::DepthInterpolation;
 
-const EPS: f32 = 5e-3;
+const EPS: f64 = 5e-3;
 
 //https://www.scratchapixel.com/lessons/3d-basic-rendering/rasterization-practical-implementation/rasterization-stage.html
 
 /**
  * Computes the area of the parallelogram spanned by the intrinsic triangle using the determinant
  */
-fn edge_function(a: &Vector2<f32>, b: &Vector2<f32>, p: &Vector2<f32>) -> f32 {
+fn edge_function<F: Float>(a: &Vector2<F>, b: &Vector2<F>, p: &Vector2<F>) -> F {
     let b_p = p-b;
     let b_a = a-b;
-    let mat = Matrix2::<f32>::from_rows(&[b_p.transpose(),b_a.transpose()]);
+    let mat = Matrix2::<F>::from_rows(&[b_p.transpose(),b_a.transpose()]);
     mat.determinant()
 }
 
 /**
- *  * Assume the triangle vertices a,b are in counter-clockwise winding order -> Fix this. GLTF can be any winding order
+ * Barycentric coordinates of p and whether p lies inside the triangle. Both winding orders are accepted.
  */
-pub fn pixel_within_triangle_and_barycentric(triangle: &Triangle<2>, p: &Vector2<f32>) -> (f32,f32,f32,bool) {
+pub fn pixel_within_triangle_and_barycentric<F: Float>(triangle: &Triangle<F,2>, p: &Vector2<F>) -> (F,F,F,bool) {
     let area = edge_function(&triangle.get_v0(),&triangle.get_v1(),&triangle.get_v2());
-    let f = match area > 0.0 {
-        true => 1.0,
-        false => -1.0
-    };
 
-    let w2 = edge_function(&triangle.get_v0(),&triangle.get_v1(),p)*f/area;
-    let w0 = edge_function(&triangle.get_v1(),&triangle.get_v2(),p)*f/area;
-    let w1 = edge_function(&triangle.get_v2(),&triangle.get_v0(),p)*f/area;
+    // The sign of the area cancels with the sign of the edge functions for points inside the triangle
+    let w2 = edge_function(&triangle.get_v0(),&triangle.get_v1(),p)/area;
+    let w0 = edge_function(&triangle.get_v1(),&triangle.get_v2(),p)/area;
+    let w1 = edge_function(&triangle.get_v2(),&triangle.get_v0(),p)/area;
 
-    let inside = w2 >= -EPS && w0 >= -EPS && w1 >= -EPS;
+    let eps = float::from_f64::<F>(EPS);
+    let inside = w2 >= -eps && w0 >= -eps && w1 >= -eps;
 
     (w0,w1,w2,inside)
 }
 
 /**
  * Returns all tuples (w0, w1, w2, p) consisting of the baryentric coordiantes (w0, w1, w2) of a pixel (p),
- * for all pixels inside the  given triangle.
+ * for all pixels inside the  given triangle. The search is clipped to the screen.
  */
-pub fn calc_all_pixels_within_triangle(triangle: &Triangle<2>) -> Vec<(f32,f32,f32,Vector2<f32>)> {
+pub fn calc_all_pixels_within_triangle<F: Float>(triangle: &Triangle<F,2>, screen_width: usize, screen_height: usize) -> Vec<(F,F,F,Vector2<F>)> {
     let (min, max) = triangle.calculate_boudning_box();
-    let min_x_f = min.x.floor() as usize;
-    let min_y_f = min.y.floor() as usize;
-    let max_x_f = max.x.ceil() as usize;
-    let max_y_f = max.y.ceil() as usize;
+    let min_x_f = float::to_usize(min.x.floor());
+    let min_y_f = float::to_usize(min.y.floor());
+    let max_x_f = float::to_usize(max.x.ceil()).min(screen_width);
+    let max_y_f = float::to_usize(max.y.ceil()).min(screen_height);
+    let half = float::from_f64::<F>(0.5);
 
     let y_range = min_y_f..max_y_f;
 
-    y_range.map(|y| {
+    y_range.flat_map(|y| {
         let x_range = min_x_f..max_x_f;
-        let y_c = y as f32 + 0.5f32;
+        let y_c = float::from_usize::<F>(y) + half;
         x_range.map(move |x| {
-            let x_c = x as f32 + 0.5f32;
+            let x_c = float::from_usize::<F>(x) + half;
             Vector2::new(x_c,y_c)
         })
-    }).flatten()
-    .map(|p| (pixel_within_triangle_and_barycentric(&triangle,&p),p))
+    })
+    .map(|p| (pixel_within_triangle_and_barycentric(triangle,&p),p))
     .filter(|&((_,_,_,inside),_)| inside)
     .map(|((w0,w1,w2,_),p)| (w0,w1,w2,p)).collect()
 }
 
 /**
- * Calcualte the inv depth for all pixels inside a triangle using perspective correct interpolation
+ * Calcualte the depth for all pixels inside a triangle. Perspective interpolation is linear in inverse depth,
+ * affine interpolation is linear in depth.
  */
-pub fn calc_inv_z_for_all_pixels(barycentric_pixels: &Vec<(f32,f32,f32)>, triangle3d: &Triangle<3>) -> Vec<f32> {
-    barycentric_pixels.iter().map(|(w0,w1,w2)| {
-        let inv_z = (w0 / triangle3d.get_v0().z) + (w1 / triangle3d.get_v1().z) + (w2 / triangle3d.get_v2().z);
-        inv_z
+pub fn calc_depth_for_all_pixels<F: Float>(barycentric_pixels: &[(F,F,F)], vertex_depths: &Vector3<F>, depth_interpolation: DepthInterpolation) -> Vec<F> {
+    barycentric_pixels.iter().map(|&(w0,w1,w2)| {
+        match depth_interpolation {
+            DepthInterpolation::Perspective => F::one()/((w0 / vertex_depths.x) + (w1 / vertex_depths.y) + (w2 / vertex_depths.z)),
+            DepthInterpolation::Affine => w0*vertex_depths.x + w1*vertex_depths.y + w2*vertex_depths.z
+        }
     }).collect()
 }
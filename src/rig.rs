@@ -0,0 +1,146 @@
+extern crate nalgebra as na;
+
+use na::Isometry3;
+use crate::camera::{Camera,CameraDescription};
+use crate::camera_model::CameraModel;
+use crate::float::Float;
+
+/**
+ * A camera rigidly mounted on a rig. The extrinsics transform from the sensor frame to the body frame of the rig.
+ */
+#[derive(Debug,Clone,PartialEq)]
+pub struct Sensor<F: Float, C: CameraModel<F>> {
+    name: String,
+    sensor_to_body: Isometry3<F>,
+    description: CameraDescription<F,C>
+}
+
+impl<F: Float, C: CameraModel<F>> Sensor<F,C> {
+    pub fn new(name: &str, sensor_to_body: Isometry3<F>, description: CameraDescription<F,C>) -> Sensor<F,C> {
+        Sensor {name: name.to_string(), sensor_to_body, description}
+    }
+
+    pub fn get_name(&self) -> &str {&self.name}
+    pub fn get_sensor_to_body(&self) -> &Isometry3<F> {&self.sensor_to_body}
+    pub fn get_description(&self) -> &CameraDescription<F,C> {&self.description}
+}
+
+/**
+ * Identifies the rig, sensor and body pose a view was captured with. The sensor id is the index of the sensor within its rig,
+ * the frame id the index of the body pose.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct RigMembership<F: Float> {
+    rig_id: usize,
+    sensor_id: usize,
+    frame_id: usize,
+    sensor_to_body: Isometry3<F>
+}
+
+impl<F: Float> RigMembership<F> {
+    pub fn new(rig_id: usize, sensor_id: usize, frame_id: usize, sensor_to_body: Isometry3<F>) -> RigMembership<F> {
+        RigMembership {rig_id, sensor_id, frame_id, sensor_to_body}
+    }
+
+    pub fn get_rig_id(&self) -> usize {self.rig_id}
+    pub fn get_sensor_id(&self) -> usize {self.sensor_id}
+    pub fn get_frame_id(&self) -> usize {self.frame_id}
+    pub fn get_sensor_to_body(&self) -> &Isometry3<F> {&self.sensor_to_body}
+}
+
+/**
+ * Sensors with fixed extrinsics relative to a common body frame, e.g. a stereo pair.
+ * Moving the body generates synchronized views of all sensors.
+ */
+#[derive(Debug,Clone,PartialEq)]
+pub struct Rig<F: Float, C: CameraModel<F>> {
+    id: usize,
+    sensors: Vec<Sensor<F,C>>
+}
+
+impl<F: Float, C: CameraModel<F> + Clone> Rig<F,C> {
+    pub fn new(id: usize, sensors: Vec<Sensor<F,C>>) -> Rig<F,C> {
+        assert!(!sensors.is_empty(), "Rig needs at least one sensor");
+        Rig {id, sensors}
+    }
+
+    /**
+     * Two identical cameras displaced along the body x axis, the left camera at the body origin
+     */
+    pub fn stereo(id: usize, baseline: F, description: CameraDescription<F,C>) -> Rig<F,C> {
+        Rig::new(id, vec![
+            Sensor::new("left", Isometry3::identity(), description.clone()),
+            Sensor::new("right", Isometry3::translation(baseline, F::zero(), F::zero()), description)
+        ])
+    }
+
+    pub fn get_id(&self) -> usize {self.id}
+    pub fn get_sensors(&self) -> &Vec<Sensor<F,C>> {&self.sensors}
+
+    /**
+     * Cameras of all sensors for the body pose (body to world) of a frame.
+     * Camera ids start at first_camera_id and follow the sensor order.
+     */
+    pub fn cameras_at(&self, frame_id: usize, body_pose: &Isometry3<F>, first_camera_id: usize) -> Vec<Camera<F,C>> {
+        self.sensors.iter().enumerate().map(|(sensor_id,sensor)| {
+            let name = format!("rig_{}_{}_{}", self.id, sensor.get_name(), frame_id);
+            Camera::new(first_camera_id + sensor_id, &name, body_pose*sensor.get_sensor_to_body(), sensor.get_description().clone())
+                .with_rig(Some(RigMembership::new(self.id, sensor_id, frame_id, *sensor.get_sensor_to_body())))
+        }).collect()
+    }
+
+    /**
+     * Synchronized cameras for every body pose of a trajectory, frame major.
+     * The camera of sensor s in frame f has the id f*sensor_count + s.
+     */
+    pub fn generate_cameras(&self, body_poses: &[Isometry3<F>]) -> Vec<Camera<F,C>> {
+        body_poses.iter().enumerate()
+            .flat_map(|(frame_id,body_pose)| self.cameras_at(frame_id, body_pose, frame_id*self.sensors.len()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use na::Vector3;
+    use crate::camera::Resolution;
+    use crate::camera_model::Pinhole;
+    use crate::io::{Format,serialize_feature_matches_with_format,deserialize_feature_matches_with_format};
+    use crate::sequence::generate_rig_cameras;
+    use crate::test_util::TempPath;
+
+    #[test]
+    fn stereo_rig_cameras() {
+        let description = CameraDescription::new(Pinhole::from_focal_and_principal_point(-300.0, -300.0, 320.0, 240.0, 0.0), Resolution::new(640, 480));
+        let rig = Rig::stereo(3, 0.2, description);
+        let body_view = |time: f64| Isometry3::new(Vector3::new(time, -0.5*time, 1.0), Vector3::new(0.0, 0.3*time, 0.1));
+        let cameras = generate_rig_cameras(&rig, &body_view, 0.0, 1.0, 4.0);
+        assert_eq!(cameras.len(), 10);
+
+        for (i, camera) in cameras.iter().enumerate() {
+            let (frame_id, sensor_id) = (i/2, i%2);
+            let membership = camera.get_rig().unwrap();
+            assert_eq!(camera.get_id(), i);
+            assert_eq!((membership.get_rig_id(), membership.get_sensor_id(), membership.get_frame_id()), (3, sensor_id, frame_id));
+            let body_pose = body_view(camera.get_timestamp().unwrap()).inverse();
+            let expected = body_pose*rig.get_sensors()[sensor_id].get_sensor_to_body();
+            assert!((camera.get_pose().to_homogeneous() - expected.to_homogeneous()).norm() < 1e-12);
+        }
+
+        let camera_features = crate::generate_matches(&cameras, &cameras.iter().map(|_| vec![]).collect::<Vec<_>>());
+        for format in [Format::Yaml, Format::Json, Format::Binary] {
+            let path = TempPath::new(&format!("stereo_rig.{}", format.extension()));
+            serialize_feature_matches_with_format(&path, &camera_features, format).unwrap();
+            let loaded = deserialize_feature_matches_with_format::<f64>(&path, format).unwrap();
+            for (camera, cf) in cameras.iter().zip(&loaded) {
+                let (membership, loaded_membership) = (camera.get_rig().unwrap(), cf.get_rig().unwrap());
+                assert_eq!(cf.get_cam_id(), camera.get_id());
+                assert_eq!((loaded_membership.get_rig_id(), loaded_membership.get_sensor_id(), loaded_membership.get_frame_id()),
+                    (membership.get_rig_id(), membership.get_sensor_id(), membership.get_frame_id()));
+                assert!((loaded_membership.get_sensor_to_body().to_homogeneous() - membership.get_sensor_to_body().to_homogeneous()).norm() < 1e-12);
+                assert!((cf.get_pose().to_homogeneous() - camera.get_pose().to_homogeneous()).norm() < 1e-12);
+            }
+        }
+    }
+}
@@ -0,0 +1,196 @@
+extern crate nalgebra as na;
+
+use std::collections::HashMap;
+use na::{Isometry3,Point3,Matrix3x4};
+use crate::camera::CameraDescription;
+use crate::camera_features::CameraFeatures;
+use crate::camera_model::CameraModel;
+use crate::feature::Feature;
+use crate::filter::FilterType;
+use crate::float::{self,Float};
+use crate::landmark::Landmark;
+use crate::{ProjectedPoint,deduplicated_ids,filter_projected_points};
+
+const MAX_ROW_ITERATIONS: usize = 20;
+
+/**
+ * A camera trajectory that can be evaluated at any time. Returns the view (world to camera) transform.
+ */
+pub trait ContinuousTrajectory<F: Float> {
+    fn view_at(&self, time: F) -> Isometry3<F>;
+}
+
+impl<F: Float, T: Fn(F) -> Isometry3<F>> ContinuousTrajectory<F> for T {
+    fn view_at(&self, time: F) -> Isometry3<F> {
+        self(time)
+    }
+}
+
+/**
+ * Interpolates the camera poses between timestamped views, linear in translation and spherical in rotation.
+ * Times before the first or after the last view are clamped.
+ */
+#[derive(Debug,Clone,PartialEq)]
+pub struct InterpolatedTrajectory<F: Float> {
+    // (time, camera to world) sorted by time
+    poses: Vec<(F,Isometry3<F>)>
+}
+
+impl<F: Float> InterpolatedTrajectory<F> {
+    pub fn new(timestamped_views: &[(F,Isometry3<F>)]) -> InterpolatedTrajectory<F> {
+        assert!(!timestamped_views.is_empty(), "Trajectory needs at least one view");
+        let mut poses = timestamped_views.iter().map(|(t,view)| (*t,view.inverse())).collect::<Vec<_>>();
+        poses.sort_by(|(a,_),(b,_)| a.partial_cmp(b).expect("Timestamps have to be comparable"));
+        InterpolatedTrajectory {poses}
+    }
+}
+
+impl<F: Float> ContinuousTrajectory<F> for InterpolatedTrajectory<F> {
+    fn view_at(&self, time: F) -> Isometry3<F> {
+        let next = self.poses.partition_point(|(t,_)| *t <= time);
+        let pose = match next {
+            0 => self.poses[0].1,
+            n if n == self.poses.len() => self.poses[n-1].1,
+            n => {
+                let (t0,pose_0) = &self.poses[n-1];
+                let (t1,pose_1) = &self.poses[n];
+                pose_0.lerp_slerp(pose_1, (time-*t0)/(*t1-*t0))
+            }
+        };
+        pose.inverse()
+    }
+}
+
+/**
+ * Row timing of a rolling shutter image. Row v is exposed at frame_start + line_delay*v.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct RollingShutter<F: Float> {
+    frame_start: F,
+    line_delay: F
+}
+
+impl<F: Float> RollingShutter<F> {
+    pub fn new(frame_start: F, line_delay: F) -> RollingShutter<F> {
+        RollingShutter {frame_start, line_delay}
+    }
+
+    pub fn get_frame_start(&self) -> F {self.frame_start}
+    pub fn get_line_delay(&self) -> F {self.line_delay}
+
+    /**
+     * Exposure time of a (sub-pixel) row
+     */
+    pub fn row_time(&self, row: F) -> F {
+        self.frame_start + self.line_delay*row
+    }
+}
+
+/**
+ * Projects every landmark with the pose at the time its own image row is exposed.
+ * The row is found by fixed point iteration starting from the frame start, points which do not converge are not projected.
+ * Returns the projected points and model depths as project_points, and the capture time of every landmark.
+ */
+pub fn project_points_rolling_shutter<F: Float, C: CameraModel<F>, T: ContinuousTrajectory<F>>(indexed_landmarks: &[Landmark<F>], camera_model: &C, trajectory: &T, shutter: &RollingShutter<F>) -> (Vec<ProjectedPoint<F>>, Vec<F>, Vec<F>) {
+    let mut points_screen_with_idx = Vec::<ProjectedPoint<F>>::with_capacity(indexed_landmarks.len());
+    let mut depths = Vec::<F>::with_capacity(indexed_landmarks.len());
+    let mut capture_times = Vec::<F>::with_capacity(indexed_landmarks.len());
+    for (id,landmark) in deduplicated_ids(indexed_landmarks).into_iter().zip(indexed_landmarks.iter()) {
+        let point = Point3::from(*landmark.get_position());
+        let mut time = shutter.get_frame_start();
+        let mut p_cam = trajectory.view_at(time)*point;
+        let mut projection = None;
+        for _ in 0..MAX_ROW_ITERATIONS {
+            let Some(p_image) = camera_model.project(&p_cam.coords) else {break};
+            let row_time = shutter.row_time(p_image.y);
+            if (row_time-time).abs() <= shutter.get_line_delay().abs()*F::default_epsilon().sqrt()*(F::one() + p_image.y.abs()) {
+                projection = Some((id,p_image));
+                break;
+            }
+            time = row_time;
+            p_cam = trajectory.view_at(time)*point;
+        }
+        points_screen_with_idx.push(projection);
+        depths.push(camera_model.depth(&p_cam.coords));
+        capture_times.push(time);
+    }
+    (points_screen_with_idx, depths, capture_times)
+}
+
+/**
+ * Visible features of a rolling shutter view. Visibility is tested with the row dependent poses
+ * and every feature records its capture time.
+ */
+pub fn filter_screen_points_for_rolling_shutter_view<F: Float, C: CameraModel<F>, T: ContinuousTrajectory<F>>(indexed_landmarks: &[Landmark<F>], camera: &CameraDescription<F,C>, trajectory: &T, shutter: &RollingShutter<F>, filter_type: FilterType) -> Vec<(usize,Feature<F>)> {
+    let (points_screen_with_idx, depths, capture_times) = project_points_rolling_shutter(indexed_landmarks, camera.get_camera_model(), trajectory, shutter);
+    let capture_time_map = points_screen_with_idx.iter().zip(capture_times)
+        .filter_map(|(p,time)| p.map(|(id,_)| (id,time)))
+        .collect::<HashMap<usize,F>>();
+    filter_projected_points(&points_screen_with_idx, &depths, camera, filter_type).into_iter()
+        .map(|(id,f)| (id, f.with_timestamp(capture_time_map.get(&id).copied())))
+        .collect()
+}
+
+/**
+ * Camera features of a rolling shutter view. The stored view matrix and timestamp are those of the frame start.
+ */
+pub fn generate_rolling_shutter_camera_features<F: Float, C: CameraModel<F>, T: ContinuousTrajectory<F>>(indexed_landmarks: &[Landmark<F>], camera: &CameraDescription<F,C>, trajectory: &T, shutter: &RollingShutter<F>, cam_id: usize, filter_type: FilterType) -> CameraFeatures<F> {
+    let features = filter_screen_points_for_rolling_shutter_view(indexed_landmarks, camera, trajectory, shutter, filter_type);
+    let view_matrix: Matrix3x4<F> = trajectory.view_at(shutter.get_frame_start()).to_matrix().fixed_view::<3,4>(0,0).into_owned();
+    CameraFeatures::new(features.into_iter().collect(), cam_id, view_matrix, camera.get_camera_model(), camera.get_resolution())
+        .with_timestamp(Some(shutter.get_frame_start()))
+}
+
+/**
+ * Line delay which spreads the exposure of all rows over the readout time
+ */
+pub fn line_delay_from_readout_time<F: Float>(readout_time: F, image_height: usize) -> F {
+    readout_time/float::from_usize(image_height)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use na::{Vector3,Translation3,UnitQuaternion};
+    use crate::camera_model::Pinhole;
+    use crate::project_points;
+
+    fn landmarks() -> Vec<Landmark<f64>> {
+        (0..25).map(|i| Landmark::new(&i, &Vector3::new((i%5) as f64 - 2.0, (i/5) as f64 - 2.0, -6.0 - 0.1*i as f64))).collect()
+    }
+
+    fn model() -> Pinhole<f64> {
+        Pinhole::from_focal_and_principal_point(-300.0, -300.0, 320.0, 240.0, 0.0)
+    }
+
+    // World to camera, the camera moves along x and turns around y
+    fn trajectory(time: f64) -> Isometry3<f64> {
+        Isometry3::from_parts(Translation3::new(-2.0*time, 0.1, 0.0), UnitQuaternion::from_euler_angles(0.0, 0.5*time, 0.0))
+    }
+
+    #[test]
+    fn zero_line_delay_matches_global_shutter() {
+        let shutter = RollingShutter::new(0.3, 0.0);
+        let (rolling, _, capture_times) = project_points_rolling_shutter(&landmarks(), &model(), &trajectory, &shutter);
+        let view_matrix: Matrix3x4<f64> = trajectory(0.3).to_matrix().fixed_view::<3,4>(0,0).into_owned();
+        let (global, _) = project_points(&landmarks(), &model(), &view_matrix);
+        for (r,g) in rolling.iter().zip(global.iter()) {
+            let ((r_id,r_image),(g_id,g_image)) = (r.unwrap(), g.unwrap());
+            assert_eq!(r_id, g_id);
+            assert!((r_image - g_image).norm() < 1e-9);
+        }
+        assert!(capture_times.iter().all(|t| *t == 0.3));
+    }
+
+    #[test]
+    fn features_are_projected_with_the_pose_of_their_row() {
+        let shutter = RollingShutter::new(0.0, line_delay_from_readout_time(0.03, 480));
+        let (rolling, _, capture_times) = project_points_rolling_shutter(&landmarks(), &model(), &trajectory, &shutter);
+        for ((projection,time),landmark) in rolling.iter().zip(capture_times).zip(landmarks()) {
+            let (_,p_image) = projection.unwrap();
+            let expected = model().project(&(trajectory(time)*Point3::from(*landmark.get_position())).coords).unwrap();
+            assert!((p_image - expected).norm() < 1e-6);
+            assert!((shutter.row_time(p_image.y) - time).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,326 @@
+extern crate nalgebra as na;
+
+use std::collections::BTreeMap;
+use na::{Vector2,Vector3,Isometry3,UnitQuaternion,Translation3};
+use rand::Rng;
+use serde::{Serialize,Deserialize};
+use crate::camera::{Camera,CameraDescription};
+use crate::camera_model::CameraModel;
+use crate::convention::Convention;
+use crate::dataset::Dataset;
+use crate::filter::FilterType;
+use crate::float::{self,Float};
+use crate::landmark::Landmark;
+use crate::schema::{MeshInfo,GenerationParameters};
+use crate::{filter_screen_points_for_camera_views,generate_matches};
+
+/**
+ * Configurations in which structure from motion is ill-posed or ill-conditioned
+ */
+#[derive(Debug,Clone,Copy,PartialEq,Eq,Serialize,Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Degeneracy {
+    PureRotation,
+    PlanarScene,
+    TinyBaseline,
+    ForwardMotion,
+    CriticalSurface
+}
+
+impl Degeneracy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Degeneracy::PureRotation => "pure_rotation",
+            Degeneracy::PlanarScene => "planar_scene",
+            Degeneracy::TinyBaseline => "tiny_baseline",
+            Degeneracy::ForwardMotion => "forward_motion",
+            Degeneracy::CriticalSurface => "critical_surface"
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            Degeneracy::PureRotation => "All cameras share one centre. Views are related by a homography, the essential matrix and the structure are undefined.",
+            Degeneracy::PlanarScene => "All landmarks lie on one plane. Views are related by a homography, fundamental matrix estimation is degenerate.",
+            Degeneracy::TinyBaseline => "The baseline is small compared to the scene depth. Triangulation angles are tiny and depths are ill-conditioned.",
+            Degeneracy::ForwardMotion => "The cameras translate along their optical axis. The epipole lies in the image centre, where parallax vanishes.",
+            Degeneracy::CriticalSurface => "Landmarks and camera centres lie on a hyperboloid of one sheet. Two view reconstruction is ambiguous."
+        }
+    }
+}
+
+/**
+ * Ground truth cameras and landmarks of a degenerate configuration
+ */
+#[derive(Debug,Clone)]
+pub struct Scenario<F: Float, C: CameraModel<F>> {
+    degeneracy: Degeneracy,
+    cameras: Vec<Camera<F,C>>,
+    landmarks: Vec<Landmark<F>>,
+    parameters: BTreeMap<String,String>
+}
+
+impl<F: Float, C: CameraModel<F> + Clone> Scenario<F,C> {
+    pub fn get_degeneracy(&self) -> Degeneracy {self.degeneracy}
+    pub fn get_cameras(&self) -> &Vec<Camera<F,C>> {&self.cameras}
+    pub fn get_landmarks(&self) -> &Vec<Landmark<F>> {&self.landmarks}
+    /**
+     * Settings the scenario was generated with, e.g. the baseline
+     */
+    pub fn get_parameters(&self) -> &BTreeMap<String,String> {&self.parameters}
+
+    /**
+     * Degeneracy name, description and parameters. Stored as generation extras by to_dataset.
+     */
+    pub fn get_metadata(&self) -> BTreeMap<String,String> {
+        let mut metadata = self.parameters.clone();
+        metadata.insert("degeneracy".to_string(), self.degeneracy.name().to_string());
+        metadata.insert("description".to_string(), self.degeneracy.description().to_string());
+        metadata
+    }
+
+    /**
+     * Projects the landmarks into all cameras with the depth filter, the landmarks are not a mesh.
+     * InvalidData if the cameras use different conventions, see Dataset::try_new
+     */
+    pub fn to_dataset(&self) -> std::io::Result<Dataset<F>> {
+        let visible_features = filter_screen_points_for_camera_views(&self.landmarks, &self.cameras, FilterType::Depth);
+        let camera_features = generate_matches(&self.cameras, &visible_features);
+        let source_mesh = MeshInfo {name: format!("scenario_{}", self.degeneracy.name()), path: None, vertex_count: self.landmarks.len()};
+        let generation = GenerationParameters {filter_type: FilterType::Depth, extras: self.get_metadata()};
+        Dataset::try_new(self.landmarks.clone(), camera_features, source_mesh, generation)
+    }
+}
+
+/**
+ * Generates degenerate scenarios for one camera description. The reference camera sits at the origin and looks along -Z (OpenGL),
+ * landmarks are sampled in its view at distances scene_distance -+ scene_depth/2, away from the image border.
+ * Camera poses follow the convention of the camera model.
+ */
+#[derive(Debug,Clone)]
+pub struct ScenarioGenerator<F: Float, C: CameraModel<F>> {
+    description: CameraDescription<F,C>,
+    view_count: usize,
+    landmark_count: usize,
+    scene_distance: F,
+    scene_depth: F
+}
+
+impl<F: Float, C: CameraModel<F> + Clone> ScenarioGenerator<F,C> {
+    /**
+     * Defaults to 5 views of 200 landmarks at a distance of 10 with a depth of 4
+     */
+    pub fn new(description: CameraDescription<F,C>) -> ScenarioGenerator<F,C> {
+        ScenarioGenerator {
+            description,
+            view_count: 5,
+            landmark_count: 200,
+            scene_distance: float::from_f64(10.0),
+            scene_depth: float::from_f64(4.0)
+        }
+    }
+
+    pub fn view_count(self, view_count: usize) -> ScenarioGenerator<F,C> {
+        assert!(view_count >= 2, "A scenario needs at least two views");
+        ScenarioGenerator {view_count, ..self}
+    }
+
+    pub fn landmark_count(self, landmark_count: usize) -> ScenarioGenerator<F,C> {
+        ScenarioGenerator {landmark_count, ..self}
+    }
+
+    pub fn scene_distance(self, scene_distance: F) -> ScenarioGenerator<F,C> {
+        ScenarioGenerator {scene_distance, ..self}
+    }
+
+    pub fn scene_depth(self, scene_depth: F) -> ScenarioGenerator<F,C> {
+        ScenarioGenerator {scene_depth, ..self}
+    }
+
+    pub fn get_description(&self) -> &CameraDescription<F,C> {&self.description}
+    pub fn get_view_count(&self) -> usize {self.view_count}
+    pub fn get_landmark_count(&self) -> usize {self.landmark_count}
+    pub fn get_scene_distance(&self) -> F {self.scene_distance}
+    pub fn get_scene_depth(&self) -> F {self.scene_depth}
+
+    /**
+     * Cameras at the origin, yawing about Y over the total angle (radians)
+     */
+    pub fn pure_rotation<R: Rng>(&self, total_angle: F, rng: &mut R) -> Scenario<F,C> {
+        let poses = self.view_fractions().map(|s| Isometry3::from_parts(Translation3::identity(), UnitQuaternion::from_axis_angle(&Vector3::y_axis(), total_angle*s))).collect();
+        let landmarks = self.sample_landmarks(rng, |direction, rng| Some(direction*self.sample_distance(rng)));
+        self.scenario(Degeneracy::PureRotation, poses, landmarks, &[("total_angle", total_angle)])
+    }
+
+    /**
+     * Landmarks on the fronto-parallel plane at scene_distance, cameras spread along X over the baseline
+     */
+    pub fn planar_scene<R: Rng>(&self, baseline: F, rng: &mut R) -> Scenario<F,C> {
+        let poses = self.sideways_poses(baseline);
+        let landmarks = self.sample_landmarks(rng, |direction, _| (direction.z < F::zero()).then(|| direction*(-self.scene_distance/direction.z)));
+        self.scenario(Degeneracy::PlanarScene, poses, landmarks, &[("baseline", baseline)])
+    }
+
+    /**
+     * Cameras spread along X over a baseline that should be small compared to scene_distance
+     */
+    pub fn tiny_baseline<R: Rng>(&self, baseline: F, rng: &mut R) -> Scenario<F,C> {
+        let poses = self.sideways_poses(baseline);
+        let landmarks = self.sample_landmarks(rng, |direction, rng| Some(direction*self.sample_distance(rng)));
+        self.scenario(Degeneracy::TinyBaseline, poses, landmarks, &[("baseline", baseline), ("baseline_to_depth_ratio", baseline/self.scene_distance)])
+    }
+
+    /**
+     * Cameras advancing along -Z over the total distance, which has to stay in front of the closest landmarks
+     */
+    pub fn forward_motion<R: Rng>(&self, total_distance: F, rng: &mut R) -> Scenario<F,C> {
+        assert!(total_distance < self.scene_distance - self.scene_depth*float::from_f64::<F>(0.5), "Cameras would pass the landmarks");
+        let poses = self.view_fractions().map(|s| Isometry3::translation(F::zero(), F::zero(), -total_distance*(s + float::from_f64(0.5)))).collect();
+        let landmarks = self.sample_landmarks(rng, |direction, rng| Some(direction*self.sample_distance(rng)));
+        self.scenario(Degeneracy::ForwardMotion, poses, landmarks, &[("total_distance", total_distance)])
+    }
+
+    /**
+     * Hyperboloid of one sheet about the vertical axis through (0,0,-a) with waist radius a = scene_distance/2:
+     * ((x^2 + (z+a)^2) - y^2)/a^2 = 1. Cameras sit on its waist, spread over the baseline (arc length) around the origin,
+     * landmarks are the intersections of the reference camera's rays with the far side of the surface.
+     */
+    pub fn critical_surface<R: Rng>(&self, baseline: F, rng: &mut R) -> Scenario<F,C> {
+        let a = self.scene_distance*float::from_f64::<F>(0.5);
+        let poses = self.view_fractions().map(|s| {
+            let phi = baseline/a*s;
+            Isometry3::translation(a*phi.sin(), F::zero(), a*(phi.cos() - F::one()))
+        }).collect();
+        // The origin lies on the surface, so t = 0 is one root of the ray intersection
+        let landmarks = self.sample_landmarks(rng, |d, _| {
+            let quadratic = (d.x*d.x + d.z*d.z - d.y*d.y)/(a*a);
+            let t = -float::from_f64::<F>(2.0)*d.z/(a*quadratic);
+            (quadratic > F::zero() && t > F::zero()).then(|| d*t)
+        });
+        self.scenario(Degeneracy::CriticalSurface, poses, landmarks, &[("baseline", baseline), ("waist_radius", a)])
+    }
+
+    /**
+     * Evenly spaced in [-0.5, 0.5]
+     */
+    fn view_fractions(&self) -> impl Iterator<Item=F> {
+        let last = float::from_usize::<F>(self.view_count - 1);
+        (0..self.view_count).map(move |k| float::from_usize::<F>(k)/last - float::from_f64(0.5))
+    }
+
+    fn sideways_poses(&self, baseline: F) -> Vec<Isometry3<F>> {
+        self.view_fractions().map(|s| Isometry3::translation(baseline*s, F::zero(), F::zero())).collect()
+    }
+
+    fn sample_distance<R: Rng>(&self, rng: &mut R) -> F {
+        self.scene_distance + self.scene_depth*(float::from_f64::<F>(rng.gen::<f64>()) - float::from_f64(0.5))
+    }
+
+    /**
+     * Places landmarks along unit directions (OpenGL reference camera frame) through random pixels within the central 80 percent of the image
+     */
+    fn sample_landmarks<R: Rng, P: Fn(Vector3<F>, &mut R) -> Option<Vector3<F>>>(&self, rng: &mut R, place: P) -> Vec<Landmark<F>> {
+        let model = self.description.get_camera_model();
+        let resolution = self.description.get_resolution();
+        let mut landmarks = Vec::with_capacity(self.landmark_count);
+        let max_attempts = 100*self.landmark_count;
+        for _ in 0..max_attempts {
+            if landmarks.len() == self.landmark_count {
+                break;
+            }
+            let pixel = Vector2::new(
+                float::from_f64::<F>((0.1 + 0.8*rng.gen::<f64>())*resolution.get_width() as f64),
+                float::from_f64::<F>((0.1 + 0.8*rng.gen::<f64>())*resolution.get_height() as f64));
+            let Some(bearing) = model.unproject(&pixel) else {continue};
+            let direction = model.convention().convert_camera_vector(&bearing.normalize(), Convention::OpenGl);
+            if let Some(position) = place(direction, rng) {
+                landmarks.push(Landmark::new(&landmarks.len(), &position));
+            }
+        }
+        landmarks
+    }
+
+    fn scenario(&self, degeneracy: Degeneracy, opengl_poses: Vec<Isometry3<F>>, landmarks: Vec<Landmark<F>>, parameters: &[(&str,F)]) -> Scenario<F,C> {
+        let convention = self.description.get_camera_model().convention();
+        let cameras = opengl_poses.iter().enumerate().map(|(id,pose)| {
+            Camera::new(id, &format!("{}_{}", degeneracy.name(), id), Convention::OpenGl.convert_camera_pose(pose, convention), self.description.clone())
+        }).collect();
+        let mut parameters = parameters.iter().map(|(k,v)| (k.to_string(), v.to_string())).collect::<BTreeMap<_,_>>();
+        parameters.insert("view_count".to_string(), self.view_count.to_string());
+        parameters.insert("scene_distance".to_string(), self.scene_distance.to_string());
+        parameters.insert("scene_depth".to_string(), self.scene_depth.to_string());
+        Scenario {degeneracy, cameras, landmarks, parameters}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use na::Point3;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+    use crate::camera::Resolution;
+    use crate::camera_model::Pinhole;
+
+    fn generators() -> Vec<ScenarioGenerator<f64,Pinhole<f64>>> {
+        let model = Pinhole::from_focal_and_principal_point(-300.0, -300.0, 320.0, 240.0, 0.0);
+        [model, model.flip_convention(480.0)].into_iter()
+            .map(|model| ScenarioGenerator::new(CameraDescription::new(model, Resolution::new(640, 480))).landmark_count(50))
+            .collect()
+    }
+
+    fn centers(scenario: &Scenario<f64,Pinhole<f64>>) -> Vec<Vector3<f64>> {
+        scenario.get_cameras().iter().map(|c| c.get_pose().translation.vector).collect()
+    }
+
+    #[test]
+    fn pure_rotation_cameras_share_one_centre() {
+        for generator in generators() {
+            let scenario = generator.pure_rotation(0.5, &mut StdRng::seed_from_u64(1));
+            let centers = centers(&scenario);
+            assert_eq!(centers.len(), generator.get_view_count());
+            assert!(centers.iter().all(|c| (c - centers[0]).norm() < 1e-12));
+            let rotations = scenario.get_cameras().iter().map(|c| c.get_pose().rotation).collect::<Vec<_>>();
+            assert!((rotations[0].angle_to(&rotations[rotations.len()-1]) - 0.5).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn planar_scene_landmarks_are_coplanar() {
+        for generator in generators() {
+            let scenario = generator.planar_scene(1.0, &mut StdRng::seed_from_u64(2));
+            let points = scenario.get_landmarks().iter().map(|l| *l.get_position()).collect::<Vec<_>>();
+            assert_eq!(points.len(), generator.get_landmark_count());
+            let normal = (points[1] - points[0]).cross(&(points[2] - points[0])).normalize();
+            assert!(points.iter().all(|p| (p - points[0]).dot(&normal).abs() < 1e-9));
+            assert!(points.iter().all(|p| (p.z + generator.get_scene_distance()).abs() < 1e-9));
+        }
+    }
+
+    #[test]
+    fn critical_surface_points_lie_on_the_hyperboloid() {
+        for generator in generators() {
+            let scenario = generator.critical_surface(2.0, &mut StdRng::seed_from_u64(3));
+            let a = generator.get_scene_distance()/2.0;
+            let on_surface = |p: &Vector3<f64>| ((p.x*p.x + (p.z + a).powi(2) - p.y*p.y)/(a*a) - 1.0).abs() < 1e-9;
+            assert_eq!(scenario.get_landmarks().len(), generator.get_landmark_count());
+            assert!(scenario.get_landmarks().iter().all(|l| on_surface(l.get_position())));
+            assert!(centers(&scenario).iter().all(on_surface));
+        }
+    }
+
+    #[test]
+    fn forward_motion_keeps_landmarks_in_front() {
+        for generator in generators() {
+            let scenario = generator.forward_motion(5.0, &mut StdRng::seed_from_u64(4));
+            let forward_sign = generator.get_description().get_camera_model().convention().forward_sign::<f64>();
+            assert_eq!(scenario.get_landmarks().len(), generator.get_landmark_count());
+            for camera in scenario.get_cameras() {
+                let view = camera.get_view();
+                assert!(scenario.get_landmarks().iter().all(|l| (view*Point3::from(*l.get_position())).z*forward_sign > 0.0));
+            }
+            let dataset = scenario.to_dataset().unwrap();
+            assert_eq!(dataset.get_generation_parameters().extras["degeneracy"], "forward_motion");
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use std::collections::BTreeMap;
+use std::io::{Error,ErrorKind};
+use serde::{Serialize, Deserialize};
+use crate::camera::Resolution;
+use crate::filter::FilterType;
+use crate::io::Format;
+use crate::float::{self,Float};
+use crate::camera_model::pinhole;
+use crate::convention::Convention;
+
+/**
+ * Version of the serialized dataset layout. Files written before the schema was introduced are treated as version 0.
+ * Version 2 stores sub-pixel feature positions alongside the pixel cell used for occlusion.
+ * Version 3 describes cameras by model name and parameter vector instead of an intrinsic matrix.
+ * Version 4 adds the unit bearing vector of every feature.
+ * Version 5 adds the capture timestamp of rolling shutter features.
+ * Version 6 records the coordinate convention of every camera and of the dataset.
+ * Version 7 records the rig, sensor and frame of views captured by a rig.
+ * Version 8 references EuRoC style IMU measurement and ground truth files from the manifest.
+ * Version 9 adds the capture timestamp of every camera.
+ * Version 10 references a perturbed initialisation of the cameras and landmarks from the manifest.
+ * Text formats store values with the full precision of the scalar type they were generated with.
+ */
+pub const SCHEMA_VERSION: u32 = 10;
+
+/**
+ * Anonymous tuple layout of the unversioned format: (cam_id, row major view matrix, row major intrinsic matrix, [(landmark_id,(x,y))])
+ */
+pub type LegacyCameraFeaturesSerial = (usize, [f32;12], [f32;9], Vec<(usize,(usize,usize))>);
+pub type LegacyLandmarkSerial = (usize, [f32;3]);
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+#[serde(bound = "F: Float")]
+pub struct FeatureSerial<F: Float> {
+    pub landmark_id: usize,
+    pub x: F,
+    pub y: F,
+    /// Pixel cell used for occlusion. Missing before version 2, where x and y were already integer pixels
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pixel: Option<[usize;2]>,
+    /// Unit vector towards the landmark in the camera frame. Missing before version 4
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bearing: Option<[F;3]>,
+    /// Capture time of a rolling shutter feature. Missing before version 5
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<F>
+}
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+#[serde(bound = "F: Float")]
+pub struct CameraFeaturesSerial<F: Float> {
+    pub cam_id: usize,
+    pub camera_model: String,
+    /// Parameter layout is defined by the camera model. Missing before version 3
+    #[serde(default)]
+    pub camera_parameters: Vec<F>,
+    /// Missing for cameras loaded from the unversioned format
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<Resolution>,
+    /// Convention of the view matrix, camera model and features. Missing before version 6, where it follows from the camera model
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub convention: Option<Convention>,
+    /// Row major 3x4 matrix
+    pub view_matrix: [F;12],
+    /// Only present for views captured by a rig. Missing before version 7
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rig: Option<RigMembershipSerial<F>>,
+    /// Capture time of the view in seconds. Missing before version 9
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<F>,
+    /// Row major 3x3 pinhole matrix. Only present before version 3
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intrinsic_matrix: Option<[F;9]>,
+    pub features: Vec<FeatureSerial<F>>
+}
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+#[serde(bound = "F: Float")]
+pub struct RigMembershipSerial<F: Float> {
+    pub rig_id: usize,
+    pub sensor_id: usize,
+    pub frame_id: usize,
+    /// Row major 3x4 extrinsics from the sensor to the rig body frame
+    pub sensor_to_body: [F;12]
+}
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+#[serde(bound = "F: Float")]
+pub struct CameraFeaturesDocument<F: Float> {
+    pub version: u32,
+    pub cameras: Vec<CameraFeaturesSerial<F>>
+}
+
+/**
+ * Binary layout of FeatureSerial. Bincode encodes fields by position, so unlike the text formats every field is always written.
+ */
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+#[serde(bound = "F: Float")]
+pub struct FeatureBinarySerial<F: Float> {
+    pub landmark_id: usize,
+    pub x: F,
+    pub y: F,
+    pub pixel: Option<[usize;2]>,
+    pub bearing: Option<[F;3]>,
+    pub timestamp: Option<F>
+}
+
+/**
+ * Binary layout of CameraFeaturesSerial, every field is always written
+ */
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+#[serde(bound = "F: Float")]
+pub struct CameraFeaturesBinarySerial<F: Float> {
+    pub cam_id: usize,
+    pub camera_model: String,
+    pub camera_parameters: Vec<F>,
+    pub resolution: Option<Resolution>,
+    pub convention: Option<Convention>,
+    pub view_matrix: [F;12],
+    pub rig: Option<RigMembershipSerial<F>>,
+    pub timestamp: Option<F>,
+    pub intrinsic_matrix: Option<[F;9]>,
+    pub features: Vec<FeatureBinarySerial<F>>
+}
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+#[serde(bound = "F: Float")]
+pub struct CameraFeaturesBinaryDocument<F: Float> {
+    pub version: u32,
+    pub cameras: Vec<CameraFeaturesBinarySerial<F>>
+}
+
+impl<F: Float> From<FeatureSerial<F>> for FeatureBinarySerial<F> {
+    fn from(f: FeatureSerial<F>) -> FeatureBinarySerial<F> {
+        FeatureBinarySerial {landmark_id: f.landmark_id, x: f.x, y: f.y, pixel: f.pixel, bearing: f.bearing, timestamp: f.timestamp}
+    }
+}
+
+impl<F: Float> From<FeatureBinarySerial<F>> for FeatureSerial<F> {
+    fn from(f: FeatureBinarySerial<F>) -> FeatureSerial<F> {
+        FeatureSerial {landmark_id: f.landmark_id, x: f.x, y: f.y, pixel: f.pixel, bearing: f.bearing, timestamp: f.timestamp}
+    }
+}
+
+impl<F: Float> From<CameraFeaturesSerial<F>> for CameraFeaturesBinarySerial<F> {
+    fn from(c: CameraFeaturesSerial<F>) -> CameraFeaturesBinarySerial<F> {
+        CameraFeaturesBinarySerial {
+            cam_id: c.cam_id,
+            camera_model: c.camera_model,
+            camera_parameters: c.camera_parameters,
+            resolution: c.resolution,
+            convention: c.convention,
+            view_matrix: c.view_matrix,
+            rig: c.rig,
+            timestamp: c.timestamp,
+            intrinsic_matrix: c.intrinsic_matrix,
+            features: c.features.into_iter().map(|f| f.into()).collect()
+        }
+    }
+}
+
+impl<F: Float> From<CameraFeaturesBinarySerial<F>> for CameraFeaturesSerial<F> {
+    fn from(c: CameraFeaturesBinarySerial<F>) -> CameraFeaturesSerial<F> {
+        CameraFeaturesSerial {
+            cam_id: c.cam_id,
+            camera_model: c.camera_model,
+            camera_parameters: c.camera_parameters,
+            resolution: c.resolution,
+            convention: c.convention,
+            view_matrix: c.view_matrix,
+            rig: c.rig,
+            timestamp: c.timestamp,
+            intrinsic_matrix: c.intrinsic_matrix,
+            features: c.features.into_iter().map(|f| f.into()).collect()
+        }
+    }
+}
+
+impl<F: Float> From<CameraFeaturesDocument<F>> for CameraFeaturesBinaryDocument<F> {
+    fn from(document: CameraFeaturesDocument<F>) -> CameraFeaturesBinaryDocument<F> {
+        CameraFeaturesBinaryDocument {version: document.version, cameras: document.cameras.into_iter().map(|c| c.into()).collect()}
+    }
+}
+
+impl<F: Float> From<CameraFeaturesBinaryDocument<F>> for CameraFeaturesDocument<F> {
+    fn from(document: CameraFeaturesBinaryDocument<F>) -> CameraFeaturesDocument<F> {
+        CameraFeaturesDocument {version: document.version, cameras: document.cameras.into_iter().map(|c| c.into()).collect()}
+    }
+}
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+#[serde(bound = "F: Float")]
+pub struct LandmarkSerial<F: Float> {
+    pub id: usize,
+    pub position: [F;3]
+}
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+#[serde(bound = "F: Float")]
+pub struct LandmarksDocument<F: Float> {
+    pub version: u32,
+    pub landmarks: Vec<LandmarkSerial<F>>
+}
+
+/**
+ * Any camera feature file this crate is able to read. Versioned documents are tried first.
+ */
+#[derive(Debug,Deserialize)]
+#[serde(untagged, bound = "F: Float")]
+pub enum CameraFeaturesFile<F: Float> {
+    Versioned(CameraFeaturesDocument<F>),
+    Legacy(Vec<LegacyCameraFeaturesSerial>)
+}
+
+#[derive(Debug,Deserialize)]
+#[serde(untagged, bound = "F: Float")]
+pub enum LandmarksFile<F: Float> {
+    Versioned(LandmarksDocument<F>),
+    Legacy(Vec<LegacyLandmarkSerial>)
+}
+
+/**
+ * Files written by a newer version of the crate can not be read
+ */
+fn check_version(version: u32) -> std::io::Result<()> {
+    match version <= SCHEMA_VERSION {
+        true => Ok(()),
+        false => Err(Error::new(ErrorKind::InvalidData, format!("Unsupported schema version {}, the latest supported version is {}", version, SCHEMA_VERSION)))
+    }
+}
+
+impl<F: Float> TryFrom<CameraFeaturesFile<F>> for CameraFeaturesDocument<F> {
+    type Error = Error;
+
+    fn try_from(file: CameraFeaturesFile<F>) -> std::io::Result<CameraFeaturesDocument<F>> {
+        match file {
+            CameraFeaturesFile::Versioned(document) => {
+                check_version(document.version)?;
+                Ok(document)
+            },
+            CameraFeaturesFile::Legacy(legacy) => Ok(CameraFeaturesDocument {
+                version: 0,
+                cameras: legacy.into_iter().map(|(cam_id,view_matrix,intrinsic_matrix,feature_vec)| CameraFeaturesSerial {
+                    cam_id,
+                    camera_model: pinhole::NAME.to_string(),
+                    camera_parameters: vec![],
+                    resolution: None,
+                    convention: None,
+                    view_matrix: view_matrix.map(|v| float::from_f64(v as f64)),
+                    rig: None,
+                    timestamp: None,
+                    intrinsic_matrix: Some(intrinsic_matrix.map(|v| float::from_f64(v as f64))),
+                    features: feature_vec.into_iter().map(|(landmark_id,(x,y))| FeatureSerial {landmark_id, x: float::from_usize(x), y: float::from_usize(y), pixel: Some([x,y]), bearing: None, timestamp: None}).collect()
+                }).collect()
+            })
+        }
+    }
+}
+
+impl<F: Float> TryFrom<LandmarksFile<F>> for LandmarksDocument<F> {
+    type Error = Error;
+
+    fn try_from(file: LandmarksFile<F>) -> std::io::Result<LandmarksDocument<F>> {
+        match file {
+            LandmarksFile::Versioned(document) => {
+                check_version(document.version)?;
+                Ok(document)
+            },
+            LandmarksFile::Legacy(legacy) => Ok(LandmarksDocument {
+                version: 0,
+                landmarks: legacy.into_iter().map(|(id,position)| LandmarkSerial {id, position: position.map(|v| float::from_f64(v as f64))}).collect()
+            })
+        }
+    }
+}
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct MeshInfo {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+    pub vertex_count: usize
+}
+
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct GenerationParameters {
+    pub filter_type: FilterType,
+    /// Free form description of how the views were generated e.g. trajectory type and step count
+    #[serde(default)]
+    pub extras: BTreeMap<String,String>
+}
+
+/**
+ * EuRoC style csv files of an IMU, relative to the manifest
+ */
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct ImuFiles {
+    pub data: String,
+    pub groundtruth: String,
+    pub sample_count: usize
+}
+
+/**
+ * Standard deviations of a perturbation::Perturbation
+ */
+#[derive(Debug,Clone,Copy,PartialEq,Serialize,Deserialize)]
+pub struct PerturbationSerial {
+    pub rotation_sigma: f64,
+    pub translation_sigma: f64,
+    pub focal_sigma: f64,
+    pub principal_point_sigma: f64,
+    pub landmark_sigma: f64
+}
+
+/**
+ * Perturbed camera features and landmarks in the dataset format, relative to the manifest
+ */
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct InitialisationFiles {
+    pub camera_features: String,
+    pub landmarks: String,
+    pub perturbation: PerturbationSerial
+}
+
+/**
+ * Entry point of a dataset directory. File names are relative to the directory containing the manifest.
+ */
+#[derive(Debug,Clone,PartialEq,Serialize,Deserialize)]
+pub struct DatasetManifest {
+    pub version: u32,
+    pub format: Format,
+    pub camera_features: String,
+    pub landmarks: String,
+    #[serde(default)]
+    pub images: Vec<String>,
+    pub camera_count: usize,
+    pub landmark_count: usize,
+    /// Convention shared by all cameras. Missing before version 6
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub convention: Option<Convention>,
+    /// Missing before version 8 and for datasets without IMU
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub imu: Option<ImuFiles>,
+    /// Missing before version 10 and for datasets without initialisation
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initialisation: Option<InitialisationFiles>,
+    pub source_mesh: MeshInfo,
+    pub generation: GenerationParameters
+}
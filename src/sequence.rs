@@ -0,0 +1,76 @@
+extern crate nalgebra as na;
+
+use crate::camera::{Camera,CameraDescription};
+use crate::camera_model::CameraModel;
+use crate::float::{self,Float};
+use crate::rig::Rig;
+use crate::rolling_shutter::ContinuousTrajectory;
+
+/**
+ * Capture times of a camera running at frame_rate (Hz) between start_time and end_time, both included if they fall on a frame.
+ * Times are computed from the frame index, so they do not accumulate rounding errors.
+ */
+pub fn frame_times<F: Float>(start_time: F, end_time: F, frame_rate: F) -> Vec<F> {
+    assert!(frame_rate > F::zero(), "Frame rate has to be positive");
+    if end_time < start_time {
+        return Vec::new();
+    }
+    let frame_count = float::to_usize(((end_time - start_time)*frame_rate + F::default_epsilon().sqrt()).floor()) + 1;
+    (0..frame_count).map(|k| start_time + float::from_usize::<F>(k)/frame_rate).collect()
+}
+
+/**
+ * One timestamped camera per frame, sampled from a trajectory of views (world to camera). Cameras are named frame_{id}.
+ */
+pub fn generate_cameras<F: Float, C: CameraModel<F> + Clone, T: ContinuousTrajectory<F>>(trajectory: &T, description: &CameraDescription<F,C>, start_time: F, end_time: F, frame_rate: F) -> Vec<Camera<F,C>> {
+    frame_times(start_time, end_time, frame_rate).into_iter().enumerate()
+        .map(|(id,time)| Camera::from_view(id, &format!("frame_{}", id), &trajectory.view_at(time), description.clone()).with_timestamp(Some(time)))
+        .collect()
+}
+
+/**
+ * Synchronized timestamped cameras of every rig sensor per frame. The trajectory is the view of the rig body (world to body).
+ * Camera ids follow Rig::generate_cameras.
+ */
+pub fn generate_rig_cameras<F: Float, C: CameraModel<F> + Clone, T: ContinuousTrajectory<F>>(rig: &Rig<F,C>, trajectory: &T, start_time: F, end_time: F, frame_rate: F) -> Vec<Camera<F,C>> {
+    let sensor_count = rig.get_sensors().len();
+    frame_times(start_time, end_time, frame_rate).into_iter().enumerate()
+        .flat_map(|(frame_id,time)| rig.cameras_at(frame_id, &trajectory.view_at(time).inverse(), frame_id*sensor_count).into_iter().map(move |c| c.with_timestamp(Some(time))))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use na::{Isometry3,Vector3};
+    use crate::camera::Resolution;
+    use crate::camera_model::Pinhole;
+
+    #[test]
+    fn frame_times_follow_the_frame_rate() {
+        let times = frame_times(0.0, 1.0, 30.0);
+        assert_eq!(times.len(), 31);
+        for (k, time) in times.iter().enumerate() {
+            assert_eq!(*time, k as f64/30.0);
+        }
+        // 0.4 s at 10 Hz is 4.000000000000001 frame intervals in floating point
+        assert_eq!(frame_times(0.5, 0.9, 10.0).len(), 5);
+        assert_eq!(frame_times(0.0, 0.99, 10.0).len(), 10);
+        assert!(frame_times(1.0, 0.5, 10.0).is_empty());
+    }
+
+    #[test]
+    fn cameras_are_sampled_at_the_frame_times() {
+        let description = CameraDescription::new(Pinhole::from_focal_and_principal_point(-300.0, -300.0, 320.0, 240.0, 0.0), Resolution::new(640, 480));
+        let view = |time: f64| Isometry3::new(Vector3::new(time, 0.0, -2.0*time), Vector3::new(0.0, 0.2*time, 0.0));
+        let cameras = generate_cameras(&view, &description, 2.0, 4.0, 20.0);
+        assert_eq!(cameras.len(), 41);
+        for (k, camera) in cameras.iter().enumerate() {
+            let time = 2.0 + k as f64/20.0;
+            assert_eq!(camera.get_id(), k);
+            assert_eq!(camera.get_name(), format!("frame_{}", k));
+            assert_eq!(camera.get_timestamp(), Some(time));
+            assert!((camera.get_view().to_homogeneous() - view(time).to_homogeneous()).norm() < 1e-12);
+        }
+    }
+}
@@ -0,0 +1,201 @@
+extern crate nalgebra as na;
+
+use na::{Vector3,Vector4,Isometry3,UnitQuaternion,Translation3};
+use crate::float::{self,Float};
+use crate::rolling_shutter::{ContinuousTrajectory,InterpolatedTrajectory};
+
+/**
+ * Pose and its time derivatives at one instant. Linear quantities are in the world frame,
+ * angular quantities in the body (camera) frame.
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub struct TrajectoryState<F: Float> {
+    pub pose: Isometry3<F>,
+    pub linear_velocity: Vector3<F>,
+    pub linear_acceleration: Vector3<F>,
+    pub angular_velocity: Vector3<F>,
+    pub angular_acceleration: Vector3<F>
+}
+
+/**
+ * A continuous trajectory with time derivatives
+ */
+pub trait KinematicTrajectory<F: Float>: ContinuousTrajectory<F> {
+    fn state_at(&self, time: F) -> TrajectoryState<F>;
+}
+
+/**
+ * Uniform cumulative cubic B-spline of camera to world poses, split into translation and rotation:
+ * p(t) = p_0 + sum_j b_j(u)*(p_j - p_{j-1}) and R(t) = R_0 * prod_j Exp(b_j(u)*Log(R_{j-1}^T*R_j)).
+ * The spline is twice continuously differentiable, its derivatives are evaluated analytically.
+ * Control pose i is associated with start_time + (i-1)*knot_interval, the spline is defined between the second and the second to last control pose.
+ * Times outside are clamped.
+ */
+#[derive(Debug,Clone,PartialEq)]
+pub struct BSplineTrajectory<F: Float> {
+    start_time: F,
+    knot_interval: F,
+    control_poses: Vec<Isometry3<F>>
+}
+
+impl<F: Float> BSplineTrajectory<F> {
+    pub fn new(start_time: F, knot_interval: F, control_poses: Vec<Isometry3<F>>) -> BSplineTrajectory<F> {
+        assert!(control_poses.len() >= 4, "A cubic B-spline needs at least four control poses");
+        assert!(knot_interval > F::zero(), "Knot interval has to be positive");
+        BSplineTrajectory {start_time, knot_interval, control_poses}
+    }
+
+    /**
+     * Smooth trajectory through timestamped views (world to camera). Control poses are sampled every knot_interval
+     * from the piecewise linear interpolation of the keyframes, so the spline approximates rather than interpolates them.
+     */
+    pub fn from_keyframes(timestamped_views: &[(F,Isometry3<F>)], knot_interval: F) -> BSplineTrajectory<F> {
+        assert!(!timestamped_views.is_empty(), "Trajectory needs at least one view");
+        let linear = InterpolatedTrajectory::new(timestamped_views);
+        let start_time = timestamped_views.iter().map(|(t,_)| *t).fold(timestamped_views[0].0, |a,b| a.min(b));
+        let end_time = timestamped_views.iter().map(|(t,_)| *t).fold(timestamped_views[0].0, |a,b| a.max(b));
+        let segment_count = float::to_usize(((end_time - start_time)/knot_interval - F::default_epsilon().sqrt()).ceil()).max(1);
+        let mut control_poses = (0..=segment_count)
+            .map(|i| linear.view_at(start_time + knot_interval*float::from_usize::<F>(i)).inverse())
+            .collect::<Vec<_>>();
+        // Reflected end controls make the spline pass through the end poses of linear motion
+        control_poses.push(reflect(&control_poses[segment_count-1], &control_poses[segment_count]));
+        control_poses.insert(0, reflect(&control_poses[1], &control_poses[0]));
+        BSplineTrajectory::new(start_time, knot_interval, control_poses)
+    }
+
+    pub fn get_start_time(&self) -> F {self.start_time}
+    pub fn get_knot_interval(&self) -> F {self.knot_interval}
+    pub fn get_control_poses(&self) -> &Vec<Isometry3<F>> {&self.control_poses}
+
+    pub fn get_end_time(&self) -> F {
+        self.start_time + self.knot_interval*float::from_usize(self.control_poses.len()-3)
+    }
+
+    /**
+     * Camera to world pose
+     */
+    pub fn pose_at(&self, time: F) -> Isometry3<F> {
+        self.state_at(time).pose
+    }
+}
+
+/**
+ * Reflects the pose across the mirror pose, in translation and rotation
+ */
+fn reflect<F: Float>(pose: &Isometry3<F>, mirror: &Isometry3<F>) -> Isometry3<F> {
+    let translation = mirror.translation.vector*float::from_f64::<F>(2.0) - pose.translation.vector;
+    let rotation = mirror.rotation*pose.rotation.inverse()*mirror.rotation;
+    Isometry3::from_parts(Translation3::from(translation), rotation)
+}
+
+impl<F: Float> KinematicTrajectory<F> for BSplineTrajectory<F> {
+    fn state_at(&self, time: F) -> TrajectoryState<F> {
+        let segment_count = self.control_poses.len()-3;
+        let s = (time.max(self.start_time) - self.start_time)/self.knot_interval;
+        let segment = float::to_usize(s.floor()).min(segment_count-1);
+        let u = (s - float::from_usize(segment)).min(F::one());
+        let controls = &self.control_poses[segment..segment+4];
+
+        let six = float::from_f64::<F>(6.0);
+        let (u2,u3) = (u*u, u*u*u);
+        let three = float::from_f64::<F>(3.0);
+        // Cumulative basis and its derivatives with respect to time for j = 1,2,3
+        let b = Vector4::new(F::one(), (float::from_f64::<F>(5.0) + three*u - three*u2 + u3)/six, (F::one() + three*u + three*u2 - float::from_f64::<F>(2.0)*u3)/six, u3/six);
+        let dt_inv = F::one()/self.knot_interval;
+        let db = Vector4::new(F::zero(), (three - six*u + three*u2)/six, (three + six*u - six*u2)/six, three*u2/six)*dt_inv;
+        let ddb = Vector4::new(F::zero(), u - F::one(), F::one() - float::from_f64::<F>(2.0)*u, u)*(dt_inv*dt_inv);
+
+        let mut position = controls[0].translation.vector;
+        let mut linear_velocity = Vector3::zeros();
+        let mut linear_acceleration = Vector3::zeros();
+        let mut rotation = controls[0].rotation;
+        let mut angular_velocity = Vector3::zeros();
+        let mut angular_acceleration = Vector3::zeros();
+        for j in 1..4 {
+            let delta_p = controls[j].translation.vector - controls[j-1].translation.vector;
+            position += delta_p*b[j];
+            linear_velocity += delta_p*db[j];
+            linear_acceleration += delta_p*ddb[j];
+
+            let delta_r = (controls[j-1].rotation.inverse()*controls[j].rotation).scaled_axis();
+            let increment = UnitQuaternion::from_scaled_axis(delta_r*b[j]);
+            rotation *= increment;
+            angular_velocity = increment.inverse_transform_vector(&angular_velocity) + delta_r*db[j];
+            angular_acceleration = increment.inverse_transform_vector(&angular_acceleration) + delta_r*ddb[j] + angular_velocity.cross(&delta_r)*db[j];
+        }
+
+        TrajectoryState {
+            pose: Isometry3::from_parts(Translation3::from(position), rotation),
+            linear_velocity,
+            linear_acceleration,
+            angular_velocity,
+            angular_acceleration
+        }
+    }
+}
+
+impl<F: Float> ContinuousTrajectory<F> for BSplineTrajectory<F> {
+    fn view_at(&self, time: F) -> Isometry3<F> {
+        self.pose_at(time).inverse()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const H: f64 = 1e-5;
+
+    fn spline() -> BSplineTrajectory<f64> {
+        let control_poses = (0..7).map(|i| {
+            let i = i as f64;
+            Isometry3::from_parts(Translation3::new(i, (0.7*i).sin(), 0.3*i*i), UnitQuaternion::from_euler_angles(0.4*i, (0.5*i).cos(), -0.2*i))
+        }).collect();
+        BSplineTrajectory::new(1.0, 0.5, control_poses)
+    }
+
+    fn times() -> impl Iterator<Item=f64> {
+        (1..20).map(|i| 1.0 + 0.1*i as f64 + 0.013)
+    }
+
+    #[test]
+    fn linear_derivatives() {
+        let spline = spline();
+        for t in times() {
+            let (before, state, after) = (spline.state_at(t - H), spline.state_at(t), spline.state_at(t + H));
+            let velocity = (after.pose.translation.vector - before.pose.translation.vector)/(2.0*H);
+            let acceleration = (after.linear_velocity - before.linear_velocity)/(2.0*H);
+            assert!((state.linear_velocity - velocity).norm() < 1e-6, "velocity at {}", t);
+            assert!((state.linear_acceleration - acceleration).norm() < 1e-6, "acceleration at {}", t);
+        }
+    }
+
+    #[test]
+    fn angular_derivatives() {
+        let spline = spline();
+        for t in times() {
+            let (before, state, after) = (spline.state_at(t - H), spline.state_at(t), spline.state_at(t + H));
+            // Body frame rates, R(t+h) = R(t)*Exp(w*h)
+            let angular_velocity = (before.pose.rotation.inverse()*after.pose.rotation).scaled_axis()/(2.0*H);
+            let angular_acceleration = (after.angular_velocity - before.angular_velocity)/(2.0*H);
+            assert!((state.angular_velocity - angular_velocity).norm() < 1e-6, "angular velocity at {}", t);
+            assert!((state.angular_acceleration - angular_acceleration).norm() < 1e-5, "angular acceleration at {}", t);
+        }
+    }
+
+    #[test]
+    fn continuous_at_knots() {
+        let spline = spline();
+        for knot in 1..4 {
+            let t = spline.get_start_time() + spline.get_knot_interval()*knot as f64;
+            let (before, after) = (spline.state_at(t - 1e-9), spline.state_at(t + 1e-9));
+            assert!((before.pose.translation.vector - after.pose.translation.vector).norm() < 1e-6);
+            assert!(before.pose.rotation.angle_to(&after.pose.rotation) < 1e-6);
+            assert!((before.linear_velocity - after.linear_velocity).norm() < 1e-6);
+            assert!((before.angular_velocity - after.angular_velocity).norm() < 1e-6);
+            assert!((before.linear_acceleration - after.linear_acceleration).norm() < 1e-6);
+            assert!((before.angular_acceleration - after.angular_acceleration).norm() < 1e-6);
+        }
+    }
+}
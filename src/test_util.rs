@@ -0,0 +1,40 @@
+use std::ops::Deref;
+use std::path::Path;
+use std::sync::atomic::{AtomicUsize,Ordering};
+
+static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/**
+ * Unique file or directory path in the system temp directory. Whatever was created at the path is removed on drop,
+ * also when the test fails.
+ */
+pub struct TempPath {
+    path: String
+}
+
+impl TempPath {
+    pub fn new(name: &str) -> TempPath {
+        let unique = format!("models_cv_{}_{}_{}", std::process::id(), COUNTER.fetch_add(1, Ordering::Relaxed), name);
+        TempPath {path: std::env::temp_dir().join(unique).to_string_lossy().to_string()}
+    }
+}
+
+impl Deref for TempPath {
+    type Target = str;
+
+    fn deref(&self) -> &str {&self.path}
+}
+
+impl AsRef<Path> for TempPath {
+    fn as_ref(&self) -> &Path {Path::new(&self.path)}
+}
+
+impl Drop for TempPath {
+    fn drop(&mut self) {
+        let path = Path::new(&self.path);
+        let _ = match path.is_dir() {
+            true => std::fs::remove_dir_all(path),
+            false => std::fs::remove_file(path)
+        };
+    }
+}
@@ -0,0 +1,256 @@
+extern crate nalgebra as na;
+
+use na::{Vector3,Point3,Isometry3,Translation3,UnitQuaternion,Unit};
+use rand::Rng;
+use crate::float::{self,Float};
+
+/**
+ * Where a camera of a trajectory looks: at a fixed point or along a fixed direction
+ */
+#[derive(Debug,Clone,Copy,PartialEq)]
+pub enum Look<F: Float> {
+    At(Point3<F>),
+    Along(Vector3<F>)
+}
+
+impl<F: Float> Look<F> {
+    fn target_from(&self, eye: &Point3<F>) -> Point3<F> {
+        match self {
+            Look::At(target) => *target,
+            Look::Along(direction) => eye + direction
+        }
+    }
+}
+
+/**
+ * Camera to world pose of a camera at eye looking at target, in the OpenGL convention (the camera looks along -Z, +Y is up in the image).
+ * If the viewing direction is parallel to up, the world axis least aligned with the viewing direction is used as up instead.
+ * Use Convention::convert_camera_pose for other conventions.
+ */
+pub fn look_at<F: Float>(eye: &Point3<F>, target: &Point3<F>, up: &Vector3<F>) -> Isometry3<F> {
+    try_look_at(eye, target, up).expect("Eye and target have to differ")
+}
+
+/**
+ * None if eye and target coincide, see look_at
+ */
+pub fn try_look_at<F: Float>(eye: &Point3<F>, target: &Point3<F>, up: &Vector3<F>) -> Option<Isometry3<F>> {
+    let direction = (target - eye).try_normalize(F::zero())?;
+    let up = match direction.cross(up).norm() > float::from_f64::<F>(1e-6)*up.norm() {
+        true => *up,
+        false => least_aligned_axis(&direction)
+    };
+    Some(Isometry3::look_at_rh(eye, target, &up).inverse())
+}
+
+/**
+ * Poses at eyes following look. An eye without a viewing direction, e.g. at the target, keeps the orientation of the previous pose,
+ * leading eyes take the first defined orientation.
+ */
+fn look_from_all<F: Float>(eyes: &[Point3<F>], look: Look<F>, up: &Unit<Vector3<F>>) -> Vec<Isometry3<F>> {
+    let rotations = eyes.iter().map(|eye| try_look_at(eye, &look.target_from(eye), up).map(|pose| pose.rotation)).collect::<Vec<_>>();
+    let mut previous = rotations.iter().flatten().next().copied().unwrap_or_else(UnitQuaternion::identity);
+    eyes.iter().zip(rotations).map(|(eye,rotation)| {
+        previous = rotation.unwrap_or(previous);
+        Isometry3::from_parts(Translation3::from(eye.coords), previous)
+    }).collect()
+}
+
+fn least_aligned_axis<F: Float>(v: &Vector3<F>) -> Vector3<F> {
+    let (axis,_) = v.iter().enumerate().fold((0,v.x.abs()), |(i_min,min),(i,c)| match c.abs() < min {true => (i,c.abs()), false => (i_min,min)});
+    Vector3::ith(axis, F::one())
+}
+
+/**
+ * Orthonormal basis (e1, e2) of the plane perpendicular to up, such that e1 x e2 = -up and e2 = e1 x up.
+ * For up = +Y this is (+X, +Z).
+ */
+fn horizontal_basis<F: Float>(up: &Unit<Vector3<F>>) -> (Vector3<F>,Vector3<F>) {
+    let axis = least_aligned_axis(up);
+    let e1 = (axis - up.into_inner()*up.dot(&axis)).normalize();
+    let e2 = e1.cross(up);
+    (e1,e2)
+}
+
+pub(crate) fn ratios<F: Float>(step_count: usize) -> impl Iterator<Item=F> {
+    (0..=step_count).map(move |s| match step_count {
+        0 => F::zero(),
+        _ => float::from_usize::<F>(s)/float::from_usize::<F>(step_count)
+    })
+}
+
+/**
+ * Point at the given azimuth and elevation (radians) on a sphere around center.
+ * Azimuth is measured in the plane perpendicular to up, elevation towards up.
+ */
+pub fn spherical_point<F: Float>(center: &Point3<F>, radius: F, azimuth: F, elevation: F, up: &Unit<Vector3<F>>) -> Point3<F> {
+    let (e1,e2) = horizontal_basis(up);
+    center + (e1*azimuth.cos() + e2*azimuth.sin())*(radius*elevation.cos()) + up.into_inner()*(radius*elevation.sin())
+}
+
+/**
+ * Circular arc around center at a constant elevation, looking at the center.
+ * Returns step_count+1 poses from start_azimuth to start_azimuth + arc.
+ */
+pub fn orbit<F: Float>(center: &Point3<F>, radius: F, elevation: F, start_azimuth: F, arc: F, step_count: usize, up: &Unit<Vector3<F>>) -> Vec<Isometry3<F>> {
+    ratios::<F>(step_count).map(|ratio| {
+        let eye = spherical_point(center, radius, start_azimuth + ratio*arc, elevation, up);
+        look_at(&eye, center, up)
+    }).collect()
+}
+
+/**
+ * Helix around the up axis through center, rising from start_height to end_height (relative to center) in the given number of turns.
+ * Every pose looks at the center.
+ */
+pub fn helix<F: Float>(center: &Point3<F>, radius: F, start_height: F, end_height: F, turns: F, step_count: usize, up: &Unit<Vector3<F>>) -> Vec<Isometry3<F>> {
+    let (e1,e2) = horizontal_basis(up);
+    let two_pi = float::from_f64::<F>(2.0)*F::pi();
+    ratios::<F>(step_count).map(|ratio| {
+        let azimuth = ratio*turns*two_pi;
+        let height = start_height + ratio*(end_height - start_height);
+        let eye = center + (e1*azimuth.cos() + e2*azimuth.sin())*radius + up.into_inner()*height;
+        look_at(&eye, center, up)
+    }).collect()
+}
+
+/**
+ * Spiral on a sphere around center from start_elevation to end_elevation in the given number of turns, looking at the center
+ */
+pub fn spherical_spiral<F: Float>(center: &Point3<F>, radius: F, start_elevation: F, end_elevation: F, turns: F, step_count: usize, up: &Unit<Vector3<F>>) -> Vec<Isometry3<F>> {
+    let two_pi = float::from_f64::<F>(2.0)*F::pi();
+    ratios::<F>(step_count).map(|ratio| {
+        let elevation = start_elevation + ratio*(end_elevation - start_elevation);
+        let eye = spherical_point(center, radius, ratio*turns*two_pi, elevation, up);
+        look_at(&eye, center, up)
+    }).collect()
+}
+
+/**
+ * count nearly uniformly distributed poses on a full sphere around center (Fibonacci lattice), looking at the center
+ */
+pub fn fibonacci_sphere<F: Float>(center: &Point3<F>, radius: F, count: usize, up: &Unit<Vector3<F>>) -> Vec<Isometry3<F>> {
+    let golden_angle = F::pi()*(float::from_f64::<F>(3.0) - float::from_f64::<F>(5.0).sqrt());
+    (0..count).map(|i| {
+        // Heights at the centers of count equal area bands
+        let height = F::one() - float::from_f64::<F>(2.0)*(float::from_usize::<F>(i) + float::from_f64(0.5))/float::from_usize::<F>(count);
+        let eye = spherical_point(center, radius, golden_angle*float::from_usize::<F>(i), height.asin(), up);
+        look_at(&eye, center, up)
+    }).collect()
+}
+
+/**
+ * Straight line from start to end. Returns step_count+1 poses.
+ * Look::Along(end - start) moves the camera along its optical axis. A pose at the Look::At target keeps the previous orientation.
+ */
+pub fn dolly<F: Float>(start: &Point3<F>, end: &Point3<F>, look: Look<F>, step_count: usize, up: &Unit<Vector3<F>>) -> Vec<Isometry3<F>> {
+    let eyes = ratios::<F>(step_count).map(|ratio| start + (end - start)*ratio).collect::<Vec<_>>();
+    look_from_all(&eyes, look, up)
+}
+
+/**
+ * Figure eight (lemniscate of Gerono) in the plane through eye_center facing target. 
+ * width and height are the extents of the figure, one loop is traversed in step_count steps. Every pose looks at the target.
+ */
+pub fn figure_eight<F: Float>(eye_center: &Point3<F>, target: &Point3<F>, width: F, height: F, step_count: usize, up: &Unit<Vector3<F>>) -> Vec<Isometry3<F>> {
+    let frame = look_at(eye_center, target, up);
+    let right = frame*Vector3::x();
+    let camera_up = frame*Vector3::y();
+    let two = float::from_f64::<F>(2.0);
+    ratios::<F>(step_count).map(|ratio| {
+        let t = ratio*two*F::pi();
+        let eye = eye_center + right*(width/two*t.sin()) + camera_up*(height*t.sin()*t.cos());
+        look_at(&eye, target, up)
+    }).collect()
+}
+
+/**
+ * Random walk of step_count steps of fixed length in uniformly distributed directions starting at start.
+ * Returns step_count+1 poses. Pass a seeded rng for reproducible trajectories. A pose at the Look::At target keeps the previous orientation.
+ */
+pub fn random_walk<F: Float, R: Rng>(start: &Point3<F>, step_length: F, step_count: usize, look: Look<F>, up: &Unit<Vector3<F>>, rng: &mut R) -> Vec<Isometry3<F>> {
+    let mut eyes = Vec::with_capacity(step_count+1);
+    eyes.push(*start);
+    for _ in 0..step_count {
+        let z = rng.gen_range(-1.0..=1.0f64);
+        let phi = rng.gen_range(0.0..(2.0*std::f64::consts::PI));
+        let rho = (1.0-z*z).sqrt();
+        let direction = Vector3::new(float::from_f64::<F>(rho*phi.cos()), float::from_f64::<F>(rho*phi.sin()), float::from_f64::<F>(z));
+        eyes.push(eyes[eyes.len()-1] + direction*step_length);
+    }
+    look_from_all(&eyes, look, up)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::StdRng;
+
+    fn forward(pose: &Isometry3<f64>) -> Vector3<f64> {
+        pose*(-Vector3::z())
+    }
+
+    fn assert_looks_at(pose: &Isometry3<f64>, target: &Point3<f64>) {
+        let direction = (target - Point3::from(pose.translation.vector)).normalize();
+        assert!((forward(pose) - direction).norm() < 1e-9, "{:?} does not look at {:?}", pose, target);
+    }
+
+    #[test]
+    fn look_at_points_minus_z_at_the_target() {
+        let (eye, target) = (Point3::new(1.0, 2.0, 3.0), Point3::new(-2.0, 0.5, -1.0));
+        let pose = look_at(&eye, &target, &Vector3::y());
+        assert!((Point3::from(pose.translation.vector) - eye).norm() < 1e-9);
+        assert_looks_at(&pose, &target);
+        // Image up is the projection of the world up
+        assert!((pose*Vector3::y()).y > 0.0);
+
+        for target in [Point3::new(1.0, 5.0, 3.0), Point3::new(1.0, -5.0, 3.0)] {
+            let pose = look_at(&eye, &target, &Vector3::y());
+            assert_looks_at(&pose, &target);
+            assert!((pose.rotation.to_rotation_matrix().matrix().determinant() - 1.0).abs() < 1e-9);
+        }
+        assert!(try_look_at(&eye, &eye, &Vector3::y()).is_none());
+    }
+
+    #[test]
+    fn orbit_keeps_radius_and_elevation() {
+        let (center, radius, elevation) = (Point3::<f64>::new(0.5, -1.0, 2.0), 3.0, 0.4);
+        let up = Vector3::y_axis();
+        let poses = orbit(&center, radius, elevation, 0.3, std::f64::consts::PI, 8, &up);
+        assert_eq!(poses.len(), 9);
+        for pose in &poses {
+            let offset = pose.translation.vector - center.coords;
+            assert!((offset.norm() - radius).abs() < 1e-9);
+            assert!(((offset.dot(&up)/radius).asin() - elevation).abs() < 1e-9);
+            assert_looks_at(pose, &center);
+        }
+    }
+
+    #[test]
+    fn fibonacci_sphere_points_lie_on_the_sphere() {
+        let (center, radius) = (Point3::<f64>::new(1.0, 2.0, -3.0), 2.5);
+        let poses = fibonacci_sphere(&center, radius, 50, &Vector3::z_axis());
+        assert_eq!(poses.len(), 50);
+        for pose in &poses {
+            assert!(((pose.translation.vector - center.coords).norm() - radius).abs() < 1e-9);
+            assert_looks_at(pose, &center);
+        }
+    }
+
+    #[test]
+    fn pose_at_the_target_keeps_the_orientation() {
+        let (start, end) = (Point3::<f64>::new(0.0, 0.0, 4.0), Point3::new(0.0, 0.0, 0.0));
+        let up = Vector3::y_axis();
+        let towards_end = dolly(&start, &end, Look::At(end), 4, &up);
+        assert_eq!(Point3::from(towards_end[4].translation.vector), end);
+        assert_eq!(towards_end[4].rotation, towards_end[3].rotation);
+        let from_target = dolly(&start, &end, Look::At(start), 4, &up);
+        assert_eq!(from_target[0].rotation, from_target[1].rotation);
+        assert_looks_at(&from_target[1], &start);
+
+        let walk = random_walk(&start, 0.5, 10, Look::At(start), &up, &mut StdRng::seed_from_u64(7));
+        assert_eq!(walk.len(), 11);
+        assert_eq!(walk[0].rotation, walk[1].rotation);
+    }
+}